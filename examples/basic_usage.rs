@@ -39,10 +39,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     while expired_count < 3 {
         if let Some(event) = handle.recv_event().await {
             match event {
-                TimerEvent::TimerExpired { name } => {
+                TimerEvent::TimerExpired { name, .. } => {
                     println!("Timer '{}' expired!", name);
                     expired_count += 1;
                 }
+                other => println!("Other event: {:?}", other),
             }
         }
     }
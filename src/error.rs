@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+/// Errors returned by [`TimerHandle`](crate::TimerHandle) operations
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TimerError {
+    /// The timer manager is no longer running
+    #[error("timer manager stopped")]
+    ManagerStopped,
+    /// The command channel is full; only returned by the non-blocking `try_*` methods
+    #[error("command channel full")]
+    ChannelFull,
+    /// The named timer is not armed
+    #[error("timer not found")]
+    TimerNotFound,
+}
@@ -25,14 +25,11 @@
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     let cancel_token = CancellationToken::new();
 //!     
-//!     // Create timer manager with configuration
-//!     let (manager, mut handle) = TimerManager::new(
-//!         "my_timer_manager".to_string(),
-//!         Duration::from_millis(10),  // heartbeat interval
-//!         100,                        // command buffer size
-//!         100,                        // event buffer size
-//!         cancel_token.clone(),
-//!     );
+//!     // Create timer manager, overriding only what differs from the defaults
+//!     let (manager, mut handle) = TimerManager::builder("my_timer_manager")
+//!         .heartbeat(Duration::from_millis(10))
+//!         .cancel_token(cancel_token.clone())
+//!         .build();
 //!
 //!     // Spawn the manager task
 //!     tokio::spawn(manager.run());
@@ -43,9 +40,10 @@
 //!     // Wait for timer expiration
 //!     if let Some(event) = handle.recv_event().await {
 //!         match event {
-//!             timer_manager::TimerEvent::TimerExpired { name } => {
+//!             timer_manager::TimerEvent::TimerExpired { name, .. } => {
 //!                 println!("Timer '{}' expired!", name);
 //!             }
+//!             other => println!("Other event: {:?}", other),
 //!         }
 //!     }
 //!
@@ -55,10 +53,22 @@
 //! }
 //! ```
 
+mod error;
 mod tm;
 
-pub use tm::{TimerCommand, TimerEvent, TimerHandle, TimerManager};
+pub use error::TimerError;
+pub use tm::{
+    CapacityPolicy, ClockJumpPolicy, ClosedDuringSend, Diagnostics, DurationStats, EffectiveConfig,
+    EventOverflowPolicy, RecordedCommand, RejectReason, RemainingPredicate, ShutdownPolicy,
+    StopReason, TimerCommand, TimerCommander, TimerEvent, TimerHandle, TimerLifecycleEvent,
+    TimerManager, TimerManagerBuilder, TimerMetrics, TimerStats, MIN_HEARTBEAT_INTERVAL,
+};
 
 // Re-export commonly used types for convenience
 pub use std::time::Duration;
 pub use tokio_util::sync::CancellationToken;
+
+// Compile-check the examples in the README
+#[cfg(doctest)]
+#[doc = include_str!("../README.md")]
+pub struct ReadmeDoctests;
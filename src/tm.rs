@@ -1,51 +1,1278 @@
-use std::collections::HashMap;
+use futures::Stream;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::mpsc;
-use tokio::time::{interval, MissedTickBehavior};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::time::{interval, interval_at, Interval, MissedTickBehavior};
 use tokio_util::sync::CancellationToken;
 
+use crate::error::TimerError;
+
+// With the `tracing` feature the `log::` macros below resolve to their `tracing` equivalents
+#[cfg(feature = "tracing")]
+use tracing as log;
+
+/// Log at a level chosen at runtime, with either logging backend
+#[cfg(not(feature = "tracing"))]
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)+) => {
+        ::log::log!($level, $($arg)+)
+    };
+}
+
+/// Log at a level chosen at runtime, with either logging backend
+#[cfg(feature = "tracing")]
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)+) => {
+        match $level {
+            ::log::Level::Error => tracing::error!($($arg)+),
+            ::log::Level::Warn => tracing::warn!($($arg)+),
+            ::log::Level::Info => tracing::info!($($arg)+),
+            ::log::Level::Debug => tracing::debug!($($arg)+),
+            ::log::Level::Trace => tracing::trace!($($arg)+),
+        }
+    };
+}
+
+/// Buffer size of the channel behind each [`TimerCommander::watch`] stream
+const WATCH_BUFFER_SIZE: usize = 32;
+
+/// Shortest accepted heartbeat; anything smaller turns the manager into a busy loop
+pub const MIN_HEARTBEAT_INTERVAL: Duration = Duration::from_millis(1);
+
+/// An adaptive heartbeat ticks after this fraction of the nearest remaining time
+const ADAPTIVE_HEARTBEAT_DIVISOR: u32 = 2;
+
+/// The expiry heap is rebuilt once it holds this many times more items than timers
+const EXPIRY_HEAP_COMPACT_FACTOR: usize = 4;
+
+/// Added to a timer's priority once it is within its escalation threshold
+const ESCALATION_PRIORITY_BOOST: u8 = 128;
+
 /// Simple Timer Manager for FSM communication
-pub struct TimerManager {
+///
+/// Timers may carry a payload of type `T`, handed back in `TimerEvent::TimerExpired`.
+pub struct TimerManager<T = ()> {
     /// Instance name for logging
     name: String,
 
     /// Channel for receiving timer commands
-    command_rx: mpsc::Receiver<TimerCommand>,
+    command_rx: mpsc::Receiver<TimerCommand<T>>,
 
     /// Channel for sending timer events
-    event_tx: mpsc::Sender<TimerEvent>,
+    event_tx: EventSender<T>,
+
+    /// Timer storage: timer_name -> entry
+    timers: HashMap<String, TimerEntry<T>>,
+
+    /// Min-heap of `(deadline, seq, name)` over `timers`
+    ///
+    /// Removed and re-armed timers leave stale items behind; an item is live only
+    /// while its deadline and seq match the stored entry.
+    expiry_heap: BinaryHeap<Reverse<(Instant, u64, String)>>,
+
+    /// Insertion sequence assigned to the next timer that is set
+    next_seq: u64,
 
-    /// Timer storage: timer_name -> expiration_time
-    timers: HashMap<String, Instant>,
+    /// Counter used to generate names for anonymous timers
+    next_anon_id: u64,
+
+    /// State of the generator drawing timer jitter
+    rng_state: u64,
 
     /// Heartbeat interval for timer checks
     heartbeat_interval: Duration,
     //// Cancellation token for graceful shutdown
     cancel_token: CancellationToken,
+
+    /// Gap threshold and response for clock-jump detection (disabled if `None`)
+    clock_jump: Option<(Duration, ClockJumpPolicy)>,
+
+    /// Time of the previous heartbeat tick
+    last_tick: Option<Instant>,
+
+    /// Sweep duration above which `HeartbeatLagged` is emitted (disabled if `None`)
+    heartbeat_lag: Option<Duration>,
+
+    /// Counters accumulated since start or the last `TakeMetrics`
+    metrics: TimerMetrics,
+
+    /// What to do with pending timers when `Shutdown` is received
+    shutdown_policy: ShutdownPolicy,
+
+    /// Deadline for a `WaitDrain` shutdown in progress
+    drain_deadline: Option<Instant>,
+
+    /// Watched name prefixes and the number of active timers under each
+    groups: Vec<(String, usize)>,
+
+    /// Timers to arm when `run` starts
+    preload: Vec<(String, Duration)>,
+
+    /// Per-timer lifecycle subscribers: timer_name -> senders
+    watchers: HashMap<String, Vec<mpsc::Sender<TimerLifecycleEvent>>>,
+
+    /// Minimum spacing between processed commands (unlimited if `None`)
+    command_rate_limit: Option<Duration>,
+
+    /// When the previous command was processed
+    last_command_at: Option<Instant>,
+
+    /// Tracked batch of each timer: timer_name -> batch_id
+    batch_members: HashMap<String, String>,
+
+    /// Unresolved timers per tracked batch: batch_id -> count
+    batch_pending: HashMap<String, usize>,
+
+    /// Called with the timer name when an expiration is dropped on a full event channel
+    on_dropped: Option<DroppedCallback>,
+
+    /// Bounds `(min, max)` for the adaptive heartbeat (fixed heartbeat if `None`)
+    adaptive_heartbeat: Option<(Duration, Duration)>,
+
+    /// Timers waiting for another to resolve: prerequisite_name -> dependents
+    dependents: HashMap<String, Vec<DependentTimer>>,
+
+    /// Source of the current time for timer deadlines
+    now_fn: NowFn,
+
+    /// Log level of the per-expiration log line
+    fire_log_level: ::log::LevelFilter,
+
+    /// Maximum number of active timers (unbounded if `None`)
+    max_timers: Option<usize>,
+
+    /// What to do when a new timer would exceed `max_timers`
+    capacity_policy: CapacityPolicy,
+
+    /// Stop after having no active timers for this long (never if `None`)
+    idle_shutdown: Option<Duration>,
+
+    /// When the manager last became idle (`None` while timers are active)
+    idle_since: Option<Instant>,
+
+    /// Receives expiration events that could not be delivered on `event_tx`
+    dead_letter_tx: Option<mpsc::Sender<TimerEvent<T>>>,
+
+    /// Whether heartbeat sweeps are suspended
+    heartbeat_paused: bool,
+
+    /// Expirations delivered since the manager was created, unaffected by `TakeMetrics`
+    total_fired: u64,
+
+    /// Expirations dropped since the manager was created, unaffected by `TakeMetrics`
+    dropped_events: u64,
+
+    /// When every timer was frozen by `PauseAll` (`None` while running)
+    clock_paused_at: Option<Instant>,
+
+    /// Receives a copy of every processed command that affects timer state
+    recorder: Option<mpsc::UnboundedSender<RecordedCommand<T>>>,
+
+    /// When `run` started, according to the configured time source
+    started_at: Instant,
+
+    /// Every this many ticks reconciles the expiry heap with `timers` (never if `None`)
+    full_sweep_every: Option<u32>,
+
+    /// Near-term ticks left before the next full sweep
+    ticks_until_full_sweep: u32,
+
+    /// Wake-ups of pending `TimerCommander::sleep` calls, by timer name
+    sleepers: HashMap<String, oneshot::Sender<()>>,
+
+    /// What to do with an expiration event when the event channel is full
+    overflow_policy: EventOverflowPolicy,
+
+    /// What to do when the event channel closes during a blocking send
+    closed_during_send: ClosedDuringSend,
+
+    /// Set when the manager must stop after the current sweep
+    stop_requested: bool,
+
+    /// Set when `heartbeat_interval` changed and `run` must rebuild its heartbeat
+    heartbeat_changed: bool,
+
+    /// Whether to emit lifecycle events such as `TimerReplaced`
+    lifecycle_events: bool,
+
+    /// Window within which identical re-sets of an active timer are ignored
+    coalesce_window: Option<Duration>,
+
+    /// When each timer was last set through `SetTimer`, and for how long
+    last_set: HashMap<String, (Instant, Duration)>,
+
+    /// Gracefully cancelled timers whose cancellation has not taken effect yet
+    grace_cancelled: HashMap<String, GraceCancelled<T>>,
+
+    /// Expirations held back under `EventOverflowPolicy::DropOldest`, oldest first
+    overflow: VecDeque<(String, TimerEvent<T>)>,
+
+    /// Paused timers and the time they had left when paused
+    paused: HashMap<String, (TimerEntry<T>, Duration)>,
+
+    /// At most this many new timers per window (unlimited if `None`)
+    creation_rate_limit: Option<(usize, Duration)>,
+
+    /// Start of the current creation window and timers created in it
+    creation_window: Option<(Instant, usize)>,
+}
+
+/// Outcome of arming a timer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Insertion {
+    /// A new timer was added
+    Added,
+    /// An existing timer with the same name was replaced
+    Replaced { old_remaining: Duration },
+    /// The timer was not armed because the manager is at capacity
+    Rejected,
+}
+
+/// Function returning the current time, used for timer deadlines
+type NowFn = Arc<dyn Fn() -> Instant + Send + Sync>;
+
+/// Current time on Tokio's clock, which follows `tokio::time::pause` and `advance`
+fn tokio_now() -> Instant {
+    tokio::time::Instant::now().into_std()
+}
+
+/// First deadline after `now` in the series `previous + k * interval`, k >= 1
+///
+/// Whole periods that passed while the timer could not fire are skipped rather
+/// than fired in a burst. `None` if the deadline is too far out to represent.
+fn next_recurrence(previous: Instant, interval: Duration, now: Instant) -> Option<Instant> {
+    let missed = now.saturating_duration_since(previous).as_nanos() / interval.as_nanos();
+    let offset = u64::try_from(interval.as_nanos() * (missed + 1)).ok()?;
+    previous.checked_add(Duration::from_nanos(offset))
+}
+
+/// A timer armed only once its prerequisite resolves
+#[derive(Debug, Clone)]
+struct DependentTimer {
+    name: String,
+    duration: Duration,
+    /// Whether to arm even if the prerequisite is cancelled instead of firing
+    arm_on_cancel: bool,
+}
+
+/// Callback invoked for each expiration dropped on a full event channel
+type DroppedCallback = Box<dyn Fn(&str) + Send + Sync>;
+
+/// A gracefully cancelled timer, held until its grace period ends
+struct GraceCancelled<T> {
+    entry: TimerEntry<T>,
+    /// Time the timer had left, if it was paused when cancelled
+    paused_remaining: Option<Duration>,
+    /// When the grace period ends and the cancellation takes effect
+    until: Instant,
+}
+
+/// A single armed timer
+#[derive(Debug, Clone)]
+struct TimerEntry<T> {
+    /// When the timer fires
+    expires_at: Instant,
+    /// Monotonic insertion sequence, used to break deadline ties
+    seq: u64,
+    /// Re-arm period of a recurring timer (`None` for one-shot timers)
+    interval: Option<Duration>,
+    /// Number of times a recurring timer has fired since it was set
+    iteration: u64,
+    /// When the timer was first set, kept across resets
+    created_at: Instant,
+    /// When the timer was last set or reset
+    reset_at: Instant,
+    /// Base priority; higher fires first among timers expiring in the same sweep
+    priority: u8,
+    /// Remaining time below which the priority is boosted (`None` never escalates)
+    escalate_below: Option<Duration>,
+    /// Value handed back in the `TimerExpired` event
+    payload: Option<T>,
+    /// Duration the timer was last set for (the interval of a recurring timer)
+    scheduled: Duration,
+}
+
+impl<T> TimerEntry<T> {
+    /// Priority at `now`, including any escalation boost
+    fn effective_priority(&self, now: Instant) -> u8 {
+        let remaining = self.expires_at.saturating_duration_since(now);
+        match self.escalate_below {
+            Some(threshold) if remaining < threshold => {
+                self.priority.saturating_add(ESCALATION_PRIORITY_BOOST)
+            }
+            _ => self.priority,
+        }
+    }
+}
+
+/// Builder for configuring a [`TimerManager`]
+///
+/// Use `TimerManagerBuilder::<T>::new` for a manager whose timers carry payloads of type `T`.
+pub struct TimerManagerBuilder<T = ()> {
+    name: String,
+    heartbeat_interval: Duration,
+    command_buffer_size: usize,
+    event_buffer_size: usize,
+    cancel_token: CancellationToken,
+    clock_jump: Option<(Duration, ClockJumpPolicy)>,
+    heartbeat_lag: Option<Duration>,
+    shutdown_policy: ShutdownPolicy,
+    initial_capacity: usize,
+    groups: Vec<String>,
+    preload: Vec<(String, Duration)>,
+    command_rate_limit: Option<Duration>,
+    on_dropped: Option<DroppedCallback>,
+    adaptive_heartbeat: Option<(Duration, Duration)>,
+    now_fn: Option<NowFn>,
+    fire_log_level: ::log::LevelFilter,
+    max_timers: Option<usize>,
+    capacity_policy: CapacityPolicy,
+    idle_shutdown: Option<Duration>,
+    dead_letter_tx: Option<mpsc::Sender<TimerEvent<T>>>,
+    recorder: Option<mpsc::UnboundedSender<RecordedCommand<T>>>,
+    full_sweep_every: Option<u32>,
+    overflow_policy: EventOverflowPolicy,
+    closed_during_send: ClosedDuringSend,
+    lifecycle_events: bool,
+    coalesce_window: Option<Duration>,
+    rng_seed: Option<u64>,
+    creation_rate_limit: Option<(usize, Duration)>,
 }
 
 /// Handle for controlling the timer manager
-pub struct TimerHandle {
+///
+/// Command methods come from the wrapped [`TimerCommander`] via `Deref`.
+pub struct TimerHandle<T = ()> {
+    /// Command side of the handle
+    commander: TimerCommander<T>,
+
+    /// Channel for receiving timer events (`None` once taken)
+    event_rx: Option<mpsc::Receiver<TimerEvent<T>>>,
+}
+
+/// Command-only handle to the timer manager; cheap to clone
+#[derive(Debug, Clone)]
+pub struct TimerCommander<T = ()> {
     /// Channel for sending commands to the timer manager
-    command_tx: mpsc::Sender<TimerCommand>,
+    command_tx: mpsc::Sender<TimerCommand<T>>,
+}
+
+impl<T> std::ops::Deref for TimerHandle<T> {
+    type Target = TimerCommander<T>;
+
+    fn deref(&self) -> &TimerCommander<T> {
+        &self.commander
+    }
+}
+
+/// Callback receiving every event, see [`TimerManagerBuilder::build_with_sink`]
+type EventSink<T> = Box<dyn Fn(TimerEvent<T>) + Send + Sync>;
+
+/// Where the manager delivers its events
+enum EventSender<T> {
+    Channel(mpsc::Sender<TimerEvent<T>>),
+    Broadcast(broadcast::Sender<TimerEvent<T>>),
+    Sink(EventSink<T>),
+}
+
+impl<T: Clone + Send + 'static> EventSender<T> {
+    /// Deliver an event without waiting
+    ///
+    /// A broadcast with no subscribers counts as a full channel.
+    fn try_send(
+        &self,
+        event: TimerEvent<T>,
+    ) -> Result<(), mpsc::error::TrySendError<TimerEvent<T>>> {
+        match self {
+            EventSender::Channel(tx) => tx.try_send(event),
+            EventSender::Broadcast(tx) => {
+                tx.send(event)
+                    .map(|_| ())
+                    .map_err(|broadcast::error::SendError(event)| {
+                        mpsc::error::TrySendError::Full(event)
+                    })
+            }
+            EventSender::Sink(sink) => {
+                sink(event);
+                Ok(())
+            }
+        }
+    }
+
+    /// Wait until an mpsc channel has room (never resolves for a broadcast channel
+    /// or a sink)
+    async fn ready(&self) {
+        match self {
+            EventSender::Channel(tx) => {
+                let _ = tx.reserve().await;
+            }
+            EventSender::Broadcast(_) | EventSender::Sink(_) => std::future::pending().await,
+        }
+    }
+
+    /// Capacity of an mpsc channel (`None` for a broadcast channel or a sink)
+    fn capacity(&self) -> Option<usize> {
+        match self {
+            EventSender::Channel(tx) => Some(tx.max_capacity()),
+            EventSender::Broadcast(_) | EventSender::Sink(_) => None,
+        }
+    }
 
-    /// Channel for receiving timer events
-    event_rx: mpsc::Receiver<TimerEvent>,
+    /// Deliver an event, waiting for room in an mpsc channel
+    async fn send(
+        &self,
+        event: TimerEvent<T>,
+    ) -> Result<(), mpsc::error::TrySendError<TimerEvent<T>>> {
+        match self {
+            EventSender::Channel(tx) => tx
+                .send(event)
+                .await
+                .map_err(|mpsc::error::SendError(event)| mpsc::error::TrySendError::Closed(event)),
+            EventSender::Broadcast(_) | EventSender::Sink(_) => self.try_send(event),
+        }
+    }
 }
 
 /// Timer command enum
-#[derive(Debug, Clone)]
-pub enum TimerCommand {
-    SetTimer { name: String, duration: Duration },
-    CancelTimer { name: String },
+///
+/// With the `serde` feature, the fire-and-forget commands can be serialized, e.g.
+/// to drive a manager from another process. Commands carrying a reply channel,
+/// a predicate or an `Instant` are skipped, and fail to serialize.
+///
+/// Commands are not `Clone`, since reply channels cannot be duplicated; use
+/// [`try_clone`](Self::try_clone) to copy the fire-and-forget ones.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimerCommand<T = ()> {
+    SetTimer {
+        name: String,
+        duration: Duration,
+        /// Returned in the `TimerExpired` event; dropped if the timer is cancelled
+        payload: Option<T>,
+    },
+    /// Set a timer that fires at `deadline` (on the next sweep if it has passed)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    SetTimerAt {
+        name: String,
+        deadline: Instant,
+    },
+    /// Set a timer only if no timer (active or paused) has that name
+    ///
+    /// Otherwise the existing timer is left alone and `TimerRejected` is emitted
+    /// with `RejectReason::AlreadyExists`.
+    SetTimerIfAbsent {
+        name: String,
+        duration: Duration,
+    },
+    /// Set several timers at once (each creates new or updates existing)
+    SetTimers {
+        timers: Vec<(String, Duration)>,
+    },
+    CancelTimer {
+        name: String,
+    },
+    /// Cancel a timer, restoring its deadline if it is set again within `grace`
+    CancelTimerGraceful {
+        name: String,
+        grace: Duration,
+    },
+    /// Cancel `cancel` and set `set_name` in one step
+    SwapTimer {
+        cancel: String,
+        set_name: String,
+        set_duration: Duration,
+    },
+    /// Cancel a timer only if the predicate accepts its remaining duration,
+    /// replying with whether it was cancelled
+    #[cfg_attr(feature = "serde", serde(skip))]
+    CancelIf {
+        name: String,
+        predicate: RemainingPredicate,
+        respond_to: oneshot::Sender<bool>,
+    },
     CancelAllTimers,
+    /// Cancel every named timer in one step, replying with how many were set
+    #[cfg_attr(feature = "serde", serde(skip))]
+    CancelTimers {
+        names: Vec<String>,
+        respond_to: oneshot::Sender<usize>,
+    },
+    /// Cancel every timer whose name starts with `prefix`, replying with how many
+    /// were cancelled
+    #[cfg_attr(feature = "serde", serde(skip))]
+    CancelByPrefix {
+        prefix: String,
+        respond_to: oneshot::Sender<usize>,
+    },
+    /// Stop a timer's countdown, keeping its remaining duration
+    ///
+    /// No-op if the timer is not armed or already paused.
+    PauseTimer {
+        name: String,
+    },
+    /// Restart a paused timer's countdown with the duration it had left
+    ///
+    /// No-op if the timer is not paused.
+    ResumeTimer {
+        name: String,
+    },
+    /// Freeze every timer's countdown; commands are still processed
+    PauseAll,
+    /// Thaw timers frozen by `PauseAll`, preserving their remaining durations
+    ResumeAll,
+    /// Stop sweeping for expired timers; commands are still processed
+    PauseHeartbeat,
+    /// Resume sweeping, first firing every timer that came due while paused
+    ResumeHeartbeat,
+    /// Multiply the remaining duration of every timer by `factor`
+    ///
+    /// A negative or non-finite factor is refused with `TimerEvent::ScaleRejected`.
+    ScaleRemaining {
+        factor: f64,
+    },
+    /// Change the heartbeat interval; intervals below `MIN_HEARTBEAT_INTERVAL`
+    /// are refused with `TimerEvent::HeartbeatRejected`
+    SetHeartbeat {
+        interval: Duration,
+    },
+    /// Stop the manager according to its `ShutdownPolicy`
+    ///
+    /// Once the manager stops, commands still queued behind this one (including
+    /// further `Shutdown`s) are dropped unprocessed. While a `WaitDrain` shutdown is
+    /// in progress, repeated `Shutdown`s are ignored and do not extend the timeout.
     Shutdown,
+    /// Like `Shutdown`, replying with the names of the timers the shutdown left
+    /// unfired, soonest first
+    #[cfg_attr(feature = "serde", serde(skip))]
+    ShutdownAndReport {
+        respond_to: oneshot::Sender<Vec<String>>,
+    },
+    /// Set a batch of timers and emit `BatchComplete` once all have fired or been cancelled
+    SetBatchTracked {
+        batch_id: String,
+        timers: Vec<(String, Duration)>,
+    },
+    /// Arm `name` for `duration` once the timer `after` fires
+    ///
+    /// If `after` is cancelled instead, `name` is armed only when `arm_on_cancel` is set.
+    SetAfter {
+        name: String,
+        duration: Duration,
+        after: String,
+        arm_on_cancel: bool,
+    },
+    /// Re-arm existing timers with new durations in one step, skipping names that
+    /// are not armed, and reply with the names that were updated
+    #[cfg_attr(feature = "serde", serde(skip))]
+    ResetTimers {
+        updates: Vec<(String, Duration)>,
+        respond_to: oneshot::Sender<Vec<String>>,
+    },
+    /// Move the timer `old` to the name `new`, keeping its deadline
+    ///
+    /// Emits `TimerNotFound` if `old` is not set, or `TimerRejected` with
+    /// `RejectReason::AlreadyExists` if `new` is.
+    RenameTimer {
+        old: String,
+        new: String,
+    },
+    /// Push an armed timer's deadline back by `additional`
+    ///
+    /// Emits `TimerNotFound` instead if the timer is not armed, and `TimerRejected`
+    /// if the new deadline would be too far out to represent.
+    ExtendTimer {
+        name: String,
+        additional: Duration,
+    },
+    /// Set a timer that re-arms itself every `interval` until cancelled
+    ///
+    /// Intervals below `MIN_HEARTBEAT_INTERVAL` are refused with
+    /// `RejectReason::IntervalTooShort`, leaving any existing timer untouched.
+    SetRecurringTimer {
+        name: String,
+        interval: Duration,
+    },
+    /// Set a timer for `duration` plus a random delay of up to `jitter`
+    SetTimerWithJitter {
+        name: String,
+        duration: Duration,
+        jitter: Duration,
+    },
+    /// Set a timer with a base priority; higher priorities are delivered first
+    /// among timers expiring in the same sweep
+    SetTimerWithPriority {
+        name: String,
+        duration: Duration,
+        priority: u8,
+    },
+    /// Set a timer whose priority is boosted once less than `escalate_below` remains
+    SetTimerEscalating {
+        name: String,
+        duration: Duration,
+        escalate_below: Duration,
+    },
+    /// Set a timer and reply once it has been armed
+    #[cfg_attr(feature = "serde", serde(skip))]
+    SetTimerConfirmed {
+        name: String,
+        duration: Duration,
+        respond_to: oneshot::Sender<()>,
+    },
+    /// Set a timer under a name generated by the manager, replying with that name
+    #[cfg_attr(feature = "serde", serde(skip))]
+    SetAnonymousTimer {
+        duration: Duration,
+        respond_to: oneshot::Sender<String>,
+    },
+    /// Set an anonymous timer that completes `wake` instead of emitting an event,
+    /// replying with the generated name
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Sleep {
+        duration: Duration,
+        respond_to: oneshot::Sender<String>,
+        wake: oneshot::Sender<()>,
+    },
+    /// Set the timer `name` so that it completes `wake` instead of emitting an event
+    #[cfg_attr(feature = "serde", serde(skip))]
+    SleepNamed {
+        name: String,
+        duration: Duration,
+        wake: oneshot::Sender<()>,
+    },
+    /// Subscribe to the lifecycle of a single named timer
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Watch {
+        name: String,
+        events: mpsc::Sender<TimerLifecycleEvent>,
+    },
+    /// Reply as soon as the command is processed
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Ping {
+        respond_to: oneshot::Sender<()>,
+    },
+    /// Summarize the remaining durations of all active timers (`None` if there are none)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    DurationStats {
+        respond_to: oneshot::Sender<Option<DurationStats>>,
+    },
+    /// Cancel every timer, replying with their former remaining durations soonest first
+    #[cfg_attr(feature = "serde", serde(skip))]
+    DrainTimers {
+        respond_to: oneshot::Sender<Vec<(String, Duration)>>,
+    },
+    /// Reply with the names of all active timers, soonest first
+    #[cfg_attr(feature = "serde", serde(skip))]
+    ListTimers {
+        respond_to: oneshot::Sender<Vec<String>>,
+    },
+    /// Reply with every active timer and its remaining duration, soonest first
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Snapshot {
+        respond_to: oneshot::Sender<Vec<(String, Duration)>>,
+    },
+    /// Reply with the number of active timers
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Count {
+        respond_to: oneshot::Sender<usize>,
+    },
+    /// Reply with whether a timer is active
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Contains {
+        name: String,
+        respond_to: oneshot::Sender<bool>,
+    },
+    /// Reply with the remaining duration of a timer (`None` if it is not active)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Query {
+        name: String,
+        respond_to: oneshot::Sender<Option<Duration>>,
+    },
+    /// Reply with the timers whose remaining duration is at most `window`, soonest first
+    #[cfg_attr(feature = "serde", serde(skip))]
+    FiringWithin {
+        window: Duration,
+        respond_to: oneshot::Sender<Vec<(String, Duration)>>,
+    },
+    /// Reply with lifetime totals of active, fired and dropped timers
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Stats {
+        respond_to: oneshot::Sender<TimerStats>,
+    },
+    /// Read the current metrics
+    #[cfg_attr(feature = "serde", serde(skip))]
+    GetMetrics {
+        respond_to: oneshot::Sender<TimerMetrics>,
+    },
+    /// Read the current metrics and reset them to zero
+    #[cfg_attr(feature = "serde", serde(skip))]
+    TakeMetrics {
+        respond_to: oneshot::Sender<TimerMetrics>,
+    },
+    /// Reply with the configuration the manager is running with
+    #[cfg_attr(feature = "serde", serde(skip))]
+    QueryConfig {
+        respond_to: oneshot::Sender<EffectiveConfig>,
+    },
+    /// Reply with metrics, timers, configuration and uptime in one snapshot
+    #[cfg_attr(feature = "serde", serde(skip))]
+    FullDiagnostics {
+        respond_to: oneshot::Sender<Diagnostics>,
+    },
+    /// Emit a `TimerExpired` event for `name` without touching any timer
+    #[cfg(any(test, feature = "testing"))]
+    InjectExpiry {
+        name: String,
+    },
+}
+
+impl<T: Clone> TimerCommand<T> {
+    /// Copy of this command, or `None` if it carries a reply channel or a predicate
+    ///
+    /// Those cannot be duplicated, which is why `TimerCommand` is not `Clone`;
+    /// every fire-and-forget command can be copied this way.
+    pub fn try_clone(&self) -> Option<TimerCommand<T>> {
+        let command = match self {
+            TimerCommand::SetTimer {
+                name,
+                duration,
+                payload,
+            } => TimerCommand::SetTimer {
+                name: name.clone(),
+                duration: *duration,
+                payload: payload.clone(),
+            },
+            TimerCommand::SetTimerIfAbsent { name, duration } => TimerCommand::SetTimerIfAbsent {
+                name: name.clone(),
+                duration: *duration,
+            },
+            TimerCommand::SetTimerAt { name, deadline } => TimerCommand::SetTimerAt {
+                name: name.clone(),
+                deadline: *deadline,
+            },
+            TimerCommand::SetTimers { timers } => TimerCommand::SetTimers {
+                timers: timers.clone(),
+            },
+            TimerCommand::CancelTimer { name } => TimerCommand::CancelTimer { name: name.clone() },
+            TimerCommand::CancelTimerGraceful { name, grace } => {
+                TimerCommand::CancelTimerGraceful {
+                    name: name.clone(),
+                    grace: *grace,
+                }
+            }
+            TimerCommand::SwapTimer {
+                cancel,
+                set_name,
+                set_duration,
+            } => TimerCommand::SwapTimer {
+                cancel: cancel.clone(),
+                set_name: set_name.clone(),
+                set_duration: *set_duration,
+            },
+            TimerCommand::CancelAllTimers => TimerCommand::CancelAllTimers,
+            TimerCommand::PauseTimer { name } => TimerCommand::PauseTimer { name: name.clone() },
+            TimerCommand::ResumeTimer { name } => TimerCommand::ResumeTimer { name: name.clone() },
+            TimerCommand::PauseAll => TimerCommand::PauseAll,
+            TimerCommand::ResumeAll => TimerCommand::ResumeAll,
+            TimerCommand::PauseHeartbeat => TimerCommand::PauseHeartbeat,
+            TimerCommand::ResumeHeartbeat => TimerCommand::ResumeHeartbeat,
+            TimerCommand::ScaleRemaining { factor } => {
+                TimerCommand::ScaleRemaining { factor: *factor }
+            }
+            TimerCommand::SetHeartbeat { interval } => TimerCommand::SetHeartbeat {
+                interval: *interval,
+            },
+            TimerCommand::Shutdown => TimerCommand::Shutdown,
+            TimerCommand::SetBatchTracked { batch_id, timers } => TimerCommand::SetBatchTracked {
+                batch_id: batch_id.clone(),
+                timers: timers.clone(),
+            },
+            TimerCommand::SetAfter {
+                name,
+                duration,
+                after,
+                arm_on_cancel,
+            } => TimerCommand::SetAfter {
+                name: name.clone(),
+                duration: *duration,
+                after: after.clone(),
+                arm_on_cancel: *arm_on_cancel,
+            },
+            TimerCommand::RenameTimer { old, new } => TimerCommand::RenameTimer {
+                old: old.clone(),
+                new: new.clone(),
+            },
+            TimerCommand::ExtendTimer { name, additional } => TimerCommand::ExtendTimer {
+                name: name.clone(),
+                additional: *additional,
+            },
+            TimerCommand::SetRecurringTimer { name, interval } => TimerCommand::SetRecurringTimer {
+                name: name.clone(),
+                interval: *interval,
+            },
+            TimerCommand::SetTimerWithJitter {
+                name,
+                duration,
+                jitter,
+            } => TimerCommand::SetTimerWithJitter {
+                name: name.clone(),
+                duration: *duration,
+                jitter: *jitter,
+            },
+            TimerCommand::SetTimerWithPriority {
+                name,
+                duration,
+                priority,
+            } => TimerCommand::SetTimerWithPriority {
+                name: name.clone(),
+                duration: *duration,
+                priority: *priority,
+            },
+            TimerCommand::SetTimerEscalating {
+                name,
+                duration,
+                escalate_below,
+            } => TimerCommand::SetTimerEscalating {
+                name: name.clone(),
+                duration: *duration,
+                escalate_below: *escalate_below,
+            },
+            #[cfg(any(test, feature = "testing"))]
+            TimerCommand::InjectExpiry { name } => {
+                TimerCommand::InjectExpiry { name: name.clone() }
+            }
+            TimerCommand::SetTimerConfirmed { .. }
+            | TimerCommand::CancelTimers { .. }
+            | TimerCommand::CancelByPrefix { .. }
+            | TimerCommand::CancelIf { .. }
+            | TimerCommand::DrainTimers { .. }
+            | TimerCommand::ShutdownAndReport { .. }
+            | TimerCommand::ResetTimers { .. }
+            | TimerCommand::SetAnonymousTimer { .. }
+            | TimerCommand::Sleep { .. }
+            | TimerCommand::SleepNamed { .. }
+            | TimerCommand::Watch { .. }
+            | TimerCommand::Ping { .. }
+            | TimerCommand::DurationStats { .. }
+            | TimerCommand::ListTimers { .. }
+            | TimerCommand::Snapshot { .. }
+            | TimerCommand::Count { .. }
+            | TimerCommand::Contains { .. }
+            | TimerCommand::Query { .. }
+            | TimerCommand::FiringWithin { .. }
+            | TimerCommand::Stats { .. }
+            | TimerCommand::GetMetrics { .. }
+            | TimerCommand::TakeMetrics { .. }
+            | TimerCommand::QueryConfig { .. }
+            | TimerCommand::FullDiagnostics { .. } => return None,
+        };
+        Some(command)
+    }
+
+    /// Copy of this command for a recording, or `None` if it does not affect timers
+    ///
+    /// Responders are replaced by fresh ones whose receivers are already gone.
+    fn replay_copy(&self) -> Option<TimerCommand<T>> {
+        if let Some(command) = self.try_clone() {
+            return Some(command);
+        }
+        let command = match self {
+            TimerCommand::SetTimerConfirmed { name, duration, .. } => TimerCommand::SetTimer {
+                name: name.clone(),
+                duration: *duration,
+                payload: None,
+            },
+            TimerCommand::CancelTimers { names, .. } => TimerCommand::CancelTimers {
+                names: names.clone(),
+                respond_to: oneshot::channel().0,
+            },
+            TimerCommand::CancelByPrefix { prefix, .. } => TimerCommand::CancelByPrefix {
+                prefix: prefix.clone(),
+                respond_to: oneshot::channel().0,
+            },
+            TimerCommand::DrainTimers { .. } => TimerCommand::CancelAllTimers,
+            TimerCommand::ShutdownAndReport { .. } => TimerCommand::Shutdown,
+            TimerCommand::ResetTimers { updates, .. } => TimerCommand::ResetTimers {
+                updates: updates.clone(),
+                respond_to: oneshot::channel().0,
+            },
+            TimerCommand::SetAnonymousTimer { duration, .. } => TimerCommand::SetAnonymousTimer {
+                duration: *duration,
+                respond_to: oneshot::channel().0,
+            },
+            TimerCommand::Sleep { duration, .. } => TimerCommand::Sleep {
+                duration: *duration,
+                respond_to: oneshot::channel().0,
+                wake: oneshot::channel().0,
+            },
+            TimerCommand::SleepNamed { name, duration, .. } => TimerCommand::SleepNamed {
+                name: name.clone(),
+                duration: *duration,
+                wake: oneshot::channel().0,
+            },
+            // The predicate cannot be copied, and queries and subscriptions leave
+            // the timers untouched
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+/// A command captured by a recording manager, see [`TimerManagerBuilder::record_commands`]
+#[derive(Debug)]
+pub struct RecordedCommand<T = ()> {
+    /// Time since the recording manager started running
+    pub offset: Duration,
+    /// The command, without its original responder
+    pub command: TimerCommand<T>,
+}
+
+/// Cancels the named timer when dropped, unless `name` has been cleared
+struct CancelOnDrop<T> {
+    command_tx: mpsc::Sender<TimerCommand<T>>,
+    name: Option<String>,
+}
+
+impl<T> Drop for CancelOnDrop<T> {
+    fn drop(&mut self) {
+        if let Some(name) = self.name.take() {
+            let _ = self.command_tx.try_send(TimerCommand::CancelTimer { name });
+        }
+    }
+}
+
+/// Predicate over a timer's remaining duration, evaluated inside the manager task
+pub struct RemainingPredicate(pub Box<dyn Fn(Duration) -> bool + Send>);
+
+impl std::fmt::Debug for RemainingPredicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RemainingPredicate(..)")
+    }
+}
+
+/// Lifecycle change of a single watched timer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimerLifecycleEvent {
+    /// The timer was armed while not already active
+    Set { duration: Duration },
+    /// The timer was re-armed while already active
+    Reset { duration: Duration },
+    /// The timer fired
+    Expired,
+    /// The timer was cancelled
+    Cancelled,
+    /// The timer was evicted to make room for another
+    Evicted,
+}
+
+/// Distribution of remaining durations across active timers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DurationStats {
+    /// Number of active timers
+    pub count: usize,
+    /// Shortest remaining duration
+    pub min_remaining: Duration,
+    /// Longest remaining duration
+    pub max_remaining: Duration,
+    /// Mean remaining duration
+    pub mean_remaining: Duration,
+}
+
+/// Lifetime totals for alarming on lost expirations, see [`TimerCommander::stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimerStats {
+    /// Number of active timers
+    pub active_timers: usize,
+    /// Expirations delivered since the manager was created
+    pub total_fired: u64,
+    /// Expirations dropped since the manager was created
+    pub dropped_events: u64,
+}
+
+/// Counters describing timer manager activity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimerMetrics {
+    /// Timers set (including replacements)
+    pub timers_set: u64,
+    /// Timers removed by a cancel command
+    pub timers_cancelled: u64,
+    /// Expiration events delivered to the event channel
+    pub timers_fired: u64,
+    /// Expiration events dropped because the event channel was full or closed
+    pub events_dropped: u64,
+    /// Timers evicted to stay within the capacity limit
+    pub timers_evicted: u64,
+    /// Expiration events routed to the dead-letter channel
+    pub events_dead_lettered: u64,
+    /// Commands handled by the manager, including metrics queries
+    ///
+    /// A `TakeMetrics` request is counted in the window that starts after it.
+    pub commands_processed: u64,
+}
+
+/// Snapshot of the settings a running manager uses
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EffectiveConfig {
+    /// Timer manager instance name
+    pub name: String,
+    /// Fixed heartbeat interval
+    pub heartbeat_interval: Duration,
+    /// Whether the heartbeat is currently paused
+    pub heartbeat_paused: bool,
+    /// Bounds `(min, max)` of the adaptive heartbeat, if enabled
+    pub adaptive_heartbeat: Option<(Duration, Duration)>,
+    /// Heartbeats per full sweep, if near-term sweeps are enabled
+    pub full_sweep_every: Option<u32>,
+    /// Capacity of the command channel
+    pub command_buffer_size: usize,
+    /// Capacity of the event channel (`None` for a broadcast channel)
+    pub event_buffer_size: Option<usize>,
+    /// Minimum spacing between processed commands, if rate limited
+    pub command_rate_limit: Option<Duration>,
+    /// Handling of a full event channel
+    pub overflow_policy: EventOverflowPolicy,
+    /// Handling of an event channel that closes during a blocking send
+    pub closed_during_send: ClosedDuringSend,
+    /// Maximum number of active timers, if limited
+    pub max_timers: Option<usize>,
+    /// Handling of timers beyond `max_timers`
+    pub capacity_policy: CapacityPolicy,
+    /// Maximum new timers per window, if rate limited
+    pub creation_rate_limit: Option<(usize, Duration)>,
+    /// Handling of pending timers on shutdown
+    pub shutdown_policy: ShutdownPolicy,
+    /// Clock-jump threshold and policy, if detection is enabled
+    pub clock_jump: Option<(Duration, ClockJumpPolicy)>,
+    /// Sweep duration that counts as heartbeat lag, if detection is enabled
+    pub heartbeat_lag: Option<Duration>,
+    /// Idle time after which the manager stops, if enabled
+    pub idle_shutdown: Option<Duration>,
+    /// Log level of the per-expiration log line
+    pub fire_log_level: ::log::LevelFilter,
+    /// Whether lifecycle events such as `TimerReplaced` are emitted
+    pub lifecycle_events: bool,
+    /// Window for coalescing identical re-sets, if enabled
+    pub coalesce_window: Option<Duration>,
+    /// Watched group prefixes
+    pub watched_groups: Vec<String>,
+    /// Whether a dead-letter channel is configured
+    pub dead_letter: bool,
+    /// Whether commands are being recorded
+    pub recording: bool,
+}
+
+/// Metrics, timers and configuration of a manager, captured at the same instant
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostics {
+    /// Current metrics
+    pub metrics: TimerMetrics,
+    /// Active timers with their remaining durations, soonest first
+    pub timers: Vec<(String, Duration)>,
+    /// Effective configuration
+    pub config: EffectiveConfig,
+    /// Time since the manager started running
+    pub uptime: Duration,
 }
 
 /// Timer event enum
 #[derive(Debug, Clone)]
-pub enum TimerEvent {
-    TimerExpired { name: String },
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimerEvent<T = ()> {
+    /// A timer fired
+    ///
+    /// Timers expiring in the same sweep are reported in deadline order, earliest
+    /// first; equal deadlines are reported in the order the timers were set. Only
+    /// a higher (or escalated) priority moves a timer ahead of an earlier deadline.
+    TimerExpired {
+        name: String,
+        /// Payload the timer was set with (`None` if it was set without one)
+        ///
+        /// A recurring timer hands back a clone on every firing.
+        payload: Option<T>,
+        /// Number of timers still active after this expiration
+        ///
+        /// A recurring timer counts itself, since it stays armed. Timers armed as a
+        /// consequence of this expiration (see `set_after`) are not counted. A value
+        /// of 0 means the manager has gone idle.
+        remaining_timers: usize,
+        /// 1-based firing count of a recurring timer; always 0 for one-shot timers
+        ///
+        /// The count restarts when the timer is set again or cancelled.
+        iteration: u64,
+        /// Time from when the timer was first set until it fired
+        ///
+        /// Re-setting an active timer does not restart this clock; a recurring timer
+        /// measures from its original set on every firing.
+        armed_for: Duration,
+        /// Time from when the timer was last set or reset until it fired
+        since_last_reset: Duration,
+        /// Duration the timer was last set for; the interval for a recurring timer
+        ///
+        /// `extend_timer` and pauses move the deadline without changing this.
+        scheduled: Duration,
+    },
+    /// A sweep took longer than the heartbeat-lag threshold
+    HeartbeatLagged {
+        /// Heartbeat ticks that passed during the sweep and were skipped
+        skipped: u32,
+        /// How long the sweep took
+        elapsed: Duration,
+    },
+    /// The gap between two heartbeats exceeded the clock-jump threshold
+    ClockJump { gap: Duration },
+    /// The first timer under a watched prefix was set
+    GroupBecameNonEmpty { prefix: String },
+    /// The last timer under a watched prefix fired or was cancelled
+    GroupBecameEmpty { prefix: String },
+    /// Every timer of a tracked batch has fired or been cancelled
+    BatchComplete { batch_id: String },
+    /// A timer was removed to make room under `CapacityPolicy::EvictFurthest`
+    TimerEvicted { name: String, remaining: Duration },
+    /// The manager stopped on its own; this is the last event it sends
+    ManagerStopped { reason: StopReason },
+    /// A new timer was refused
+    TimerRejected { name: String, reason: RejectReason },
+    /// A `CancelTimer` removed an active timer (only with lifecycle events enabled)
+    TimerCancelled { name: String },
+    /// A `CancelAllTimers` removed `count` timers, paused ones and those still
+    /// within a grace period included (only with lifecycle events enabled, and
+    /// only if there were any)
+    AllTimersCancelled { count: usize },
+    /// A command addressed a timer that is not armed
+    TimerNotFound { name: String },
+    /// A `ScaleRemaining` factor was negative or not finite; no timer changed
+    ScaleRejected { factor: f64 },
+    /// A `SetHeartbeat` interval was below `MIN_HEARTBEAT_INTERVAL`; the
+    /// heartbeat is unchanged
+    HeartbeatRejected { interval: Duration },
+    /// A `SetTimer` armed a timer (only with lifecycle events enabled)
+    ///
+    /// Emitted whether or not the timer was new; when it `replaced` an active
+    /// timer, `TimerReplaced` follows.
+    TimerSet {
+        name: String,
+        duration: Duration,
+        replaced: bool,
+    },
+    /// A `SetTimer` overwrote an active timer (only with lifecycle events enabled)
+    TimerReplaced {
+        name: String,
+        /// Time the overwritten timer had left
+        old_remaining: Duration,
+        new_duration: Duration,
+    },
+}
+
+/// Why a timer was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RejectReason {
+    /// Creating it would exceed the creation rate limit
+    CreationRateLimit,
+    /// The manager already holds `max_timers` timers
+    AtCapacity,
+    /// A `SetTimerIfAbsent` named a timer that is already set
+    AlreadyExists,
+    /// A `SetRecurringTimer` interval was below `MIN_HEARTBEAT_INTERVAL`
+    IntervalTooShort,
+    /// An `ExtendTimer` would move the deadline too far out to represent
+    DeadlineOverflow,
+}
+
+/// Why the manager stopped on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StopReason {
+    /// No timers were active for the configured idle timeout
+    Idle,
+}
+
+/// Response to an anomalous gap between consecutive heartbeats
+///
+/// Such gaps typically come from a suspended host or a starved runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClockJumpPolicy {
+    /// Only emit `TimerEvent::ClockJump`
+    Notify,
+    /// Emit the event and push every deadline forward by the time lost beyond one heartbeat
+    Rephase,
+    /// Emit the event and fire every pending timer on this sweep
+    FireAll,
+}
+
+/// What happens to an expiration event when the event channel is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EventOverflowPolicy {
+    /// Drop the event (see `TimerManagerBuilder::on_dropped` and `dead_letter`)
+    #[default]
+    Drop,
+    /// Wait for room in the channel
+    ///
+    /// The manager processes no commands and fires no other timers while waiting.
+    Block,
+    /// Hold back up to a channel's worth of further events inside the manager,
+    /// dropping the oldest held-back event when that overflows
+    ///
+    /// Held-back events are delivered in order as soon as the consumer makes room.
+    /// A broadcast channel already discards its oldest events, so there this acts
+    /// like `Drop`.
+    DropOldest,
+}
+
+/// What happens when the event channel closes while a `Block` send is waiting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClosedDuringSend {
+    /// Stop the manager after the current sweep
+    Shutdown,
+    /// Keep running, but drop undeliverable events from now on as under
+    /// `EventOverflowPolicy::Drop`
+    #[default]
+    SwitchToDrop,
+    /// Keep firing the rest of the sweep, routing each event to the dead-letter
+    /// channel (or dropping it if there is none)
+    DeadLetter,
+}
+
+/// What happens when a new timer would exceed the configured maximum
+///
+/// Replacing an existing timer never counts against the limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CapacityPolicy {
+    /// Refuse the new timer, emitting `TimerEvent::TimerRejected`
+    #[default]
+    Reject,
+    /// Evict the active timer with the latest deadline to make room, emitting
+    /// `TimerEvent::TimerEvicted` for it
+    EvictFurthest,
+}
+
+/// How pending timers are handled when `Shutdown` is received
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ShutdownPolicy {
+    /// Stop immediately, discarding pending timers
+    #[default]
+    Discard,
+    /// Fire every pending timer immediately, then stop
+    FireAll,
+    /// Fire the timers whose deadline has already passed but were not swept yet,
+    /// then stop; later timers are left unfired
+    DrainExpired,
+    /// Keep running until no timers remain or `timeout` elapses, then stop
+    ///
+    /// Commands are still processed while draining, so timers set in the
+    /// meantime also delay the shutdown (up to `timeout`).
+    WaitDrain { timeout: Duration },
 }
 
 impl TimerManager {
@@ -53,7 +1280,8 @@ impl TimerManager {
     ///
     /// # Arguments
     /// * `name` - Timer manager instance name
-    /// * `heartbeat_interval` - How often to check for expired timers
+    /// * `heartbeat_interval` - How often to check for expired timers (at least
+    ///   [`MIN_HEARTBEAT_INTERVAL`], or this panics)
     /// * `command_buffer_size` - Size of command channel buffer
     /// * `event_buffer_size` - Size of event channel buffer
     ///
@@ -65,397 +1293,6685 @@ impl TimerManager {
         event_buffer_size: usize,
         cancel_token: CancellationToken,
     ) -> (Self, TimerHandle) {
-        let (command_tx, command_rx) = mpsc::channel(command_buffer_size);
-        let (event_tx, event_rx) = mpsc::channel(event_buffer_size);
+        TimerManagerBuilder::new(name)
+            .heartbeat(heartbeat_interval)
+            .command_buffer(command_buffer_size)
+            .event_buffer(event_buffer_size)
+            .cancel_token(cancel_token)
+            .build()
+    }
 
-        let manager = TimerManager {
-            name,
-            command_rx,
-            event_tx,
-            timers: HashMap::new(),
-            heartbeat_interval,
-            cancel_token,
-        };
+    /// Create a new TimerManager that arms `timers` when it starts running
+    ///
+    /// Pairs with the value returned by [`run`](Self::run) to hand the timers of a
+    /// stopped manager over to a new one.
+    pub fn new_with_timers(
+        name: String,
+        heartbeat_interval: Duration,
+        command_buffer_size: usize,
+        event_buffer_size: usize,
+        cancel_token: CancellationToken,
+        timers: Vec<(String, Duration)>,
+    ) -> (Self, TimerHandle) {
+        TimerManagerBuilder::new(name)
+            .heartbeat(heartbeat_interval)
+            .command_buffer(command_buffer_size)
+            .event_buffer(event_buffer_size)
+            .cancel_token(cancel_token)
+            .preload(timers)
+            .build()
+    }
 
-        let handle = TimerHandle {
-            command_tx,
-            event_rx,
-        };
+    /// Create a builder for a TimerManager with the given instance name
+    pub fn builder(name: impl Into<String>) -> TimerManagerBuilder {
+        TimerManagerBuilder::new(name)
+    }
 
-        (manager, handle)
+    /// Create a TimerManager with default settings whose events go to a broadcast
+    /// channel of `capacity`; see [`TimerManagerBuilder::build_with_broadcast`]
+    pub fn with_broadcast(
+        name: impl Into<String>,
+        capacity: usize,
+    ) -> (Self, TimerCommander, broadcast::Sender<TimerEvent>) {
+        TimerManagerBuilder::new(name).build_with_broadcast(capacity)
+    }
+
+    /// Create a TimerManager with default settings that hands every event to
+    /// `sink`; see [`TimerManagerBuilder::build_with_sink`]
+    pub fn new_with_sink(
+        name: impl Into<String>,
+        sink: impl Fn(TimerEvent) + Send + Sync + 'static,
+    ) -> (Self, TimerCommander) {
+        TimerManagerBuilder::new(name).build_with_sink(sink)
     }
+}
 
+impl<T: Clone + Send + 'static> TimerManager<T> {
     /// Run the timer manager
-    pub async fn run(mut self) {
+    ///
+    /// The manager sleeps until the earliest deadline, so timers fire without
+    /// waiting for a heartbeat. The heartbeat bounds how long the manager sleeps
+    /// while timers are active, which picks up deadlines reached by a custom time
+    /// source. With no timers the heartbeat stops and the manager waits for
+    /// commands, unless idle shutdown, clock-jump detection or a draining
+    /// shutdown still need it.
+    ///
+    /// When the cancellation token fires, the command channel is closed and every
+    /// command already queued is processed in order before the manager stops; any
+    /// later send fails with `TimerError::ManagerStopped`. A successfully sent
+    /// command is therefore never discarded silently, unless it is queued behind a
+    /// `Shutdown`. The manager also stops once every handle and commander has
+    /// been dropped.
+    ///
+    /// Returns the timers still active when the manager stopped, with their
+    /// remaining durations, soonest first.
+    ///
+    /// With the `tracing` feature the whole run is wrapped in a `timer_manager`
+    /// span carrying the instance `name`.
+    pub async fn run(self) -> Vec<(String, Duration)> {
+        #[cfg(feature = "tracing")]
+        let run = {
+            use tracing::Instrument;
+            let span = tracing::info_span!("timer_manager", name = %self.name);
+            self.run_loop().instrument(span)
+        };
+        #[cfg(not(feature = "tracing"))]
+        let run = self.run_loop();
+        run.await
+    }
+
+    /// Run the manager on a new thread with its own current-thread runtime
+    ///
+    /// For applications without a Tokio runtime of their own. Drive the handle
+    /// from synchronous code with the `blocking_*` and `try_*` methods. The thread
+    /// is named after the manager, and joining it returns the same timers as
+    /// [`run`](Self::run). Fails only if the runtime or the thread cannot be created.
+    pub fn spawn_on_thread(
+        self,
+    ) -> std::io::Result<std::thread::JoinHandle<Vec<(String, Duration)>>> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        std::thread::Builder::new()
+            .name(self.name.clone())
+            .spawn(move || runtime.block_on(self.run()))
+    }
+
+    async fn run_loop(mut self) -> Vec<(String, Duration)> {
         let mut heartbeat = interval(self.heartbeat_interval);
         heartbeat.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
+        for (name, duration) in std::mem::take(&mut self.preload) {
+            self.insert_timer(name, duration);
+        }
+
+        // Next tick of an adaptive heartbeat
+        let mut next_tick_at = tokio_now();
+
+        self.started_at = self.now();
         log::info!("Timer manager '{}' started", self.name);
 
         loop {
+            let next_expiry = self.next_expiry();
+            let expiry_sleep =
+                tokio::time::sleep_until(next_expiry.unwrap_or_else(tokio::time::Instant::now));
+            let heartbeat_needed = self.heartbeat_needed();
+
             tokio::select! {
                 // Handle incoming commands
-                Some(command) = self.command_rx.recv() => {
-                    match command {
-                        _ if self.cancel_token.is_cancelled() => {
-                            log::info!("Timer manager '{}' cancelled", self.name);
-                            break;
-                        }
-                        _ => {
-                            let shutdown = self.handle_command(command).await;
-                            if shutdown {
-                                break;
-                            }
+                command = self.command_rx.recv() => {
+                    let Some(command) = command else {
+                        log::info!("Timer manager '{}' shutting down - all senders dropped", self.name);
+                        break;
+                    };
+                    self.throttle_commands().await;
+                    self.record(&command);
+                    let shutdown = self.handle_command(command).await;
+                    if shutdown || self.drain_finished() || self.stop_requested {
+                        break;
+                    }
+
+                    if std::mem::take(&mut self.heartbeat_changed) {
+                        heartbeat = Self::adaptive_interval(self.heartbeat_interval);
+                        next_tick_at = tokio_now() + self.heartbeat_interval;
+                    }
+
+                    // Tighten an adaptive heartbeat if a nearer deadline appeared
+                    if let Some(period) = self.adaptive_period() {
+                        let now = tokio_now();
+                        if now + period < next_tick_at {
+                            heartbeat = Self::adaptive_interval(period);
+                            next_tick_at = now + period;
                         }
                     }
                 },
 
+                // Wake up for the earliest deadline
+                _ = expiry_sleep, if next_expiry.is_some() && !self.sweeps_suspended() => {
+                    let sweep_started = tokio_now();
+                    // A stall wakes this and the heartbeat together; rephase first
+                    self.check_clock_jump().await;
+                    self.fire_timers_due_by(self.now()).await;
+                    self.check_heartbeat_lag(sweep_started);
+                    if self.stop_requested {
+                        log::info!("Timer manager '{}' shutting down - event channel closed", self.name);
+                        break;
+                    }
+                    if self.drain_finished() {
+                        log::info!("Timer manager '{}' finished draining timers", self.name);
+                        break;
+                    }
+                },
+
                 // Check for expired timers
-                _ = heartbeat.tick() => {
+                _ = heartbeat.tick(), if heartbeat_needed && !self.sweeps_suspended() => {
+                    let sweep_started = tokio_now();
+                    self.check_clock_jump().await;
                     self.check_expired_timers().await;
+                    self.check_heartbeat_lag(sweep_started);
+                    if self.stop_requested {
+                        log::info!("Timer manager '{}' shutting down - event channel closed", self.name);
+                        break;
+                    }
+                    if self.drain_finished() {
+                        log::info!("Timer manager '{}' finished draining timers", self.name);
+                        break;
+                    }
+                    if self.idle_expired() {
+                        log::info!("Timer manager '{}' shutting down - idle", self.name);
+                        self.send_event(TimerEvent::ManagerStopped { reason: StopReason::Idle });
+                        break;
+                    }
+
+                    if let Some(period) = self.adaptive_period() {
+                        heartbeat = Self::adaptive_interval(period);
+                        next_tick_at = tokio_now() + period;
+                    }
+                },
+
+                // Hand held-back expirations to the consumer once it makes room
+                _ = self.event_tx.ready(), if !self.overflow.is_empty() => {
+                    self.flush_overflow();
                 },
 
                 // Handle cancellation token
                 _ = self.cancel_token.cancelled() => {
                     log::info!("Timer manager '{}' cancelled via token", self.name);
+                    self.process_queued_commands().await;
                     break;
                 },
-
-                // All senders dropped
-                else => {
-                    log::info!("Timer manager '{}' shutting down - all senders dropped", self.name);
-                    break;
-                }
             }
         }
 
+        self.flush_overflow();
+        // Close before unwoken `sleep_named` futures see their wake-up dropped
+        self.command_rx.close();
         log::info!("Timer manager '{}' stopped", self.name);
+        self.held_timers()
+    }
+
+    /// When the earliest timer is due, on Tokio's clock (`None` without timers)
+    fn next_expiry(&mut self) -> Option<tokio::time::Instant> {
+        let earliest = loop {
+            let Reverse((expires_at, seq, name)) = self.expiry_heap.peek()?;
+            if self.is_live(*expires_at, *seq, name) {
+                break *expires_at;
+            }
+            self.expiry_heap.pop();
+        };
+        let remaining = earliest.saturating_duration_since(self.now());
+        Some(tokio::time::Instant::now() + remaining)
+    }
+
+    /// Whether the heartbeat has work to do
+    fn heartbeat_needed(&self) -> bool {
+        !self.timers.is_empty()
+            || self.idle_shutdown.is_some()
+            || self.clock_jump.is_some()
+            || self.drain_deadline.is_some()
+            || !self.grace_cancelled.is_empty()
+    }
+
+    /// Whether expired timers must not be fired right now
+    fn sweeps_suspended(&self) -> bool {
+        self.heartbeat_paused || self.clock_paused_at.is_some()
+    }
+
+    /// Whether the manager has had no active timers for the idle timeout
+    fn idle_expired(&mut self) -> bool {
+        let Some(idle_shutdown) = self.idle_shutdown else {
+            return false;
+        };
+        if !self.timers.is_empty() || !self.paused.is_empty() {
+            self.idle_since = None;
+            return false;
+        }
+        let now = tokio_now();
+        now.duration_since(*self.idle_since.get_or_insert(now)) >= idle_shutdown
+    }
+
+    /// Send a copy of `command` to the recorder, if one is configured
+    fn record(&self, command: &TimerCommand<T>) {
+        let Some(recorder) = &self.recorder else {
+            return;
+        };
+        if let Some(command) = command.replay_copy() {
+            let offset = self.now().saturating_duration_since(self.started_at);
+            let _ = recorder.send(RecordedCommand { offset, command });
+        }
+    }
+
+    /// Feed a recorded command log into a manager at the recorded offsets
+    ///
+    /// Offsets are measured from when this is called, using Tokio's clock. To
+    /// reproduce a recording deterministically, run under paused Tokio time
+    /// (`tokio::time::pause`), which the manager's default time source follows.
+    pub async fn replay(
+        handle: &TimerCommander<T>,
+        log: impl IntoIterator<Item = RecordedCommand<T>>,
+    ) -> Result<(), TimerError> {
+        let start = tokio::time::Instant::now();
+        for RecordedCommand { offset, command } in log {
+            tokio::time::sleep_until(start + offset).await;
+            handle
+                .command_tx
+                .send(command)
+                .await
+                .map_err(|_| TimerError::ManagerStopped)?;
+        }
+        Ok(())
+    }
+
+    /// Settings the manager is currently running with
+    fn effective_config(&self) -> EffectiveConfig {
+        EffectiveConfig {
+            name: self.name.clone(),
+            heartbeat_interval: self.heartbeat_interval,
+            heartbeat_paused: self.heartbeat_paused,
+            adaptive_heartbeat: self.adaptive_heartbeat,
+            full_sweep_every: self.full_sweep_every,
+            command_buffer_size: self.command_rx.max_capacity(),
+            event_buffer_size: self.event_tx.capacity(),
+            command_rate_limit: self.command_rate_limit,
+            overflow_policy: self.overflow_policy,
+            closed_during_send: self.closed_during_send,
+            max_timers: self.max_timers,
+            capacity_policy: self.capacity_policy,
+            creation_rate_limit: self.creation_rate_limit,
+            shutdown_policy: self.shutdown_policy,
+            clock_jump: self.clock_jump,
+            heartbeat_lag: self.heartbeat_lag,
+            idle_shutdown: self.idle_shutdown,
+            fire_log_level: self.fire_log_level,
+            lifecycle_events: self.lifecycle_events,
+            coalesce_window: self.coalesce_window,
+            watched_groups: self
+                .groups
+                .iter()
+                .map(|(prefix, _)| prefix.clone())
+                .collect(),
+            dead_letter: self.dead_letter_tx.is_some(),
+            recording: self.recorder.is_some(),
+        }
+    }
+
+    /// Current time according to the configured time source
+    fn now(&self) -> Instant {
+        (self.now_fn)()
+    }
+
+    /// Period of the next adaptive heartbeat, or `None` for a fixed heartbeat
+    fn adaptive_period(&self) -> Option<Duration> {
+        let (min, mut max) = self.adaptive_heartbeat?;
+        // Ticks spaced out beyond the clock-jump threshold would each look like a jump
+        if let Some((threshold, _)) = self.clock_jump {
+            max = max.min(threshold / 2).max(min);
+        }
+        let now = self.now();
+        let period = self
+            .timers
+            .values()
+            .map(|entry| entry.expires_at.saturating_duration_since(now))
+            .min()
+            .map_or(max, |nearest| nearest / ADAPTIVE_HEARTBEAT_DIVISOR);
+        Some(period.clamp(min, max))
+    }
+
+    /// Heartbeat whose first tick is one `period` from now
+    fn adaptive_interval(period: Duration) -> Interval {
+        let mut heartbeat = interval_at(tokio::time::Instant::now() + period, period);
+        heartbeat.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        heartbeat
+    }
+
+    /// Remaining duration of every active timer, soonest first
+    fn remaining_timers(&self) -> Vec<(String, Duration)> {
+        let now = self.now();
+        let mut entries: Vec<_> = self.timers.iter().collect();
+        entries.sort_unstable_by_key(|(_, entry)| (entry.expires_at, entry.seq));
+        entries
+            .into_iter()
+            .map(|(name, entry)| {
+                (
+                    name.clone(),
+                    entry.expires_at.saturating_duration_since(now),
+                )
+            })
+            .collect()
+    }
+
+    /// Remaining durations of all active and paused timers, soonest first
+    fn held_timers(&self) -> Vec<(String, Duration)> {
+        let mut timers = self.remaining_timers();
+        timers.extend(
+            self.paused
+                .iter()
+                .map(|(name, (_, remaining))| (name.clone(), *remaining)),
+        );
+        timers.sort_by_key(|(_, remaining)| *remaining);
+        timers
+    }
+
+    /// Wait until the configured spacing since the previous command has elapsed
+    async fn throttle_commands(&mut self) {
+        if let (Some(spacing), Some(last)) = (self.command_rate_limit, self.last_command_at) {
+            let ready_at = last + spacing;
+            if ready_at > tokio_now() {
+                tokio::time::sleep_until(ready_at.into()).await;
+            }
+        }
+        self.last_command_at = Some(tokio_now());
+    }
+
+    /// Close the command channel and process the commands still queued in it
+    async fn process_queued_commands(&mut self) {
+        self.command_rx.close();
+        while let Some(command) = self.command_rx.recv().await {
+            self.record(&command);
+            if self.handle_command(command).await || self.stop_requested {
+                break;
+            }
+        }
     }
 
     /// Handle timer commands
-    async fn handle_command(&mut self, command: TimerCommand) -> bool {
+    async fn handle_command(&mut self, command: TimerCommand<T>) -> bool {
         let mut shutdown = false;
         match command {
-            TimerCommand::SetTimer { name, duration } => {
-                let expires_at = Instant::now() + duration;
-                let _was_replaced = self.timers.insert(name.clone(), expires_at).is_some();
+            TimerCommand::SetTimers { timers } => {
+                for (name, duration) in timers {
+                    self.insert_timer(name, duration);
+                }
+            }
+            TimerCommand::SetTimer {
+                name,
+                duration,
+                payload,
+            } if payload.is_none() && self.coalesced(&name, duration) => {
+                log::debug!(
+                    "Timer manager '{}' coalesced repeated set of timer '{}'",
+                    self.name,
+                    name
+                );
+            }
+            TimerCommand::SetTimer {
+                name,
+                duration,
+                payload,
+            } => {
+                let (insertion, armed_for) = match self.restore_grace_cancelled(&name) {
+                    Some(remaining) => {
+                        let restored = match self.timers.get_mut(&name) {
+                            Some(entry) => Some(entry),
+                            None => self.paused.get_mut(&name).map(|(entry, _)| entry),
+                        };
+                        if let (Some(payload), Some(entry)) = (payload, restored) {
+                            entry.payload = Some(payload);
+                        }
+                        (Insertion::Added, remaining)
+                    }
+                    None => (
+                        self.insert_entry(name.clone(), duration, None, payload),
+                        duration,
+                    ),
+                };
+                if self.coalesce_window.is_some() {
+                    self.last_set.insert(name.clone(), (self.now(), duration));
+                }
+                if insertion != Insertion::Rejected && self.lifecycle_events {
+                    self.send_event(TimerEvent::TimerSet {
+                        name: name.clone(),
+                        duration: armed_for,
+                        replaced: matches!(insertion, Insertion::Replaced { .. }),
+                    });
+                }
+                if let Insertion::Replaced { old_remaining } = insertion {
+                    if self.lifecycle_events {
+                        self.send_event(TimerEvent::TimerReplaced {
+                            name,
+                            old_remaining,
+                            new_duration: duration,
+                        });
+                    }
+                }
+            }
+            TimerCommand::SetTimerIfAbsent { name, duration } => {
+                if self.timers.contains_key(&name) || self.paused.contains_key(&name) {
+                    log::debug!(
+                        "Timer manager '{}' already has timer '{}', not replacing it",
+                        self.name,
+                        name
+                    );
+                    self.send_event(TimerEvent::TimerRejected {
+                        name,
+                        reason: RejectReason::AlreadyExists,
+                    });
+                } else {
+                    self.insert_timer(name, duration);
+                }
+            }
+            TimerCommand::SetTimerAt { name, deadline } => {
+                let duration = deadline.saturating_duration_since(self.now());
+                self.insert_timer(name, duration);
+            }
+            TimerCommand::CancelTimer { name } => {
+                if self.remove_cancelled(&name) && self.lifecycle_events {
+                    self.send_event(TimerEvent::TimerCancelled { name });
+                }
+            }
+            TimerCommand::CancelTimerGraceful { name, grace } => {
+                let held = match self.timers.remove(&name) {
+                    Some(entry) => Some((entry, None)),
+                    None => self
+                        .paused
+                        .remove(&name)
+                        .map(|(entry, remaining)| (entry, Some(remaining))),
+                };
+                if let Some((entry, paused_remaining)) = held {
+                    log::debug!(
+                        "Timer '{}' cancelled with a grace period of {:?} in manager '{}'",
+                        name,
+                        grace,
+                        self.name
+                    );
+                    let until = self.now() + grace;
+                    self.grace_cancelled.insert(
+                        name,
+                        GraceCancelled {
+                            entry,
+                            paused_remaining,
+                            until,
+                        },
+                    );
+                }
+            }
+            TimerCommand::SwapTimer {
+                cancel,
+                set_name,
+                set_duration,
+            } => {
+                self.remove_cancelled(&cancel);
+                self.insert_timer(set_name, set_duration);
+            }
+            TimerCommand::CancelIf {
+                name,
+                predicate,
+                respond_to,
+            } => {
+                let now = self.now();
+                let matches = self.timers.get(&name).is_some_and(|entry| {
+                    (predicate.0)(entry.expires_at.saturating_duration_since(now))
+                });
+                let cancelled = matches && self.remove_cancelled(&name);
+                let _ = respond_to.send(cancelled);
+            }
+            TimerCommand::CancelTimers { names, respond_to } => {
+                let _ = respond_to.send(self.cancel_named(names));
+            }
+            TimerCommand::CancelByPrefix { prefix, respond_to } => {
+                let names: Vec<String> = self
+                    .timers
+                    .keys()
+                    .chain(self.paused.keys())
+                    .filter(|name| name.starts_with(&prefix))
+                    .cloned()
+                    .collect();
+                let _ = respond_to.send(self.cancel_named(names));
+            }
+            TimerCommand::CancelAllTimers => {
+                let count = self.timers.len() + self.paused.len() + self.grace_cancelled.len();
+                self.cancel_all();
+                if count > 0 && self.lifecycle_events {
+                    self.send_event(TimerEvent::AllTimersCancelled { count });
+                }
+            }
+            TimerCommand::PauseTimer { name } => {
+                if let Some(entry) = self.timers.remove(&name) {
+                    let remaining = entry.expires_at.saturating_duration_since(self.now());
+                    self.paused.insert(name, (entry, remaining));
+                }
+            }
+            TimerCommand::ResumeTimer { name } => {
+                self.unpause(&name);
+            }
+            TimerCommand::PauseAll => {
+                if self.clock_paused_at.is_none() {
+                    log::info!("Timer manager '{}' paused all timers", self.name);
+                    self.clock_paused_at = Some(self.now());
+                }
+            }
+            TimerCommand::ResumeAll => {
+                if let Some(paused_at) = self.clock_paused_at.take() {
+                    log::info!("Timer manager '{}' resumed all timers", self.name);
+                    self.shift_deadlines_since(paused_at);
+                    // The pause is not a clock jump
+                    self.last_tick = None;
+                }
+            }
+            TimerCommand::PauseHeartbeat => {
+                log::info!("Timer manager '{}' heartbeat paused", self.name);
+                self.heartbeat_paused = true;
+            }
+            TimerCommand::ResumeHeartbeat => {
+                if self.heartbeat_paused {
+                    log::info!("Timer manager '{}' heartbeat resumed", self.name);
+                    self.heartbeat_paused = false;
+                    // The pause is not a clock jump
+                    self.last_tick = None;
+                    if !self.sweeps_suspended() {
+                        self.check_expired_timers().await;
+                    }
+                }
+            }
+            TimerCommand::ScaleRemaining { factor } => {
+                if factor.is_finite() && factor >= 0.0 {
+                    log::info!(
+                        "Timer manager '{}' scaling remaining durations by {}",
+                        self.name,
+                        factor
+                    );
+                    self.scale_remaining(factor);
+                } else {
+                    log::warn!(
+                        "Timer manager '{}' rejected scale factor {}",
+                        self.name,
+                        factor
+                    );
+                    self.send_event(TimerEvent::ScaleRejected { factor });
+                }
+            }
+            TimerCommand::SetHeartbeat { interval } => {
+                if interval < MIN_HEARTBEAT_INTERVAL {
+                    log::warn!(
+                        "Timer manager '{}' rejected heartbeat interval {:?}",
+                        self.name,
+                        interval
+                    );
+                    self.send_event(TimerEvent::HeartbeatRejected { interval });
+                } else {
+                    log::info!(
+                        "Timer manager '{}' heartbeat interval set to {:?}",
+                        self.name,
+                        interval
+                    );
+                    self.heartbeat_interval = interval;
+                    // An adaptive heartbeat keeps choosing its own period
+                    self.heartbeat_changed = self.adaptive_heartbeat.is_none();
+                }
+            }
+            TimerCommand::Shutdown => {
+                //log::info!("Timer manager '{}' shutting down", self.name);
+                shutdown = self.begin_shutdown().await;
+            }
+            TimerCommand::ShutdownAndReport { respond_to } => {
+                shutdown = self.begin_shutdown().await;
+                let abandoned = self
+                    .held_timers()
+                    .into_iter()
+                    .map(|(name, _)| name)
+                    .collect();
+                let _ = respond_to.send(abandoned);
+            }
+            TimerCommand::SetAfter {
+                name,
+                duration,
+                after,
+                arm_on_cancel,
+            } => {
+                let dependent = DependentTimer {
+                    name,
+                    duration,
+                    arm_on_cancel,
+                };
+                // A paused or gracefully cancelled prerequisite may still fire
+                let held = self.timers.contains_key(&after)
+                    || self.paused.contains_key(&after)
+                    || self.grace_cancelled.contains_key(&after);
+                if held {
+                    self.dependents.entry(after).or_default().push(dependent);
+                } else if arm_on_cancel {
+                    // A missing prerequisite is treated like a cancelled one
+                    self.insert_timer(dependent.name, dependent.duration);
+                }
+            }
+            TimerCommand::ResetTimers {
+                updates,
+                respond_to,
+            } => {
+                let mut updated = Vec::with_capacity(updates.len());
+                for (name, duration) in updates {
+                    if let Some(entry) = self.timers.get_mut(&name) {
+                        let (interval, payload) = (entry.interval, entry.payload.take());
+                        self.insert_entry(name.clone(), duration, interval, payload);
+                        updated.push(name);
+                    }
+                }
+                let _ = respond_to.send(updated);
+            }
+            TimerCommand::RenameTimer { old, new } => {
+                let held =
+                    |name: &str| self.timers.contains_key(name) || self.paused.contains_key(name);
+                if !held(&old) {
+                    log::warn!(
+                        "Timer manager '{}' cannot rename missing timer '{}'",
+                        self.name,
+                        old
+                    );
+                    self.send_event(TimerEvent::TimerNotFound { name: old });
+                } else if old != new && held(&new) {
+                    log::warn!(
+                        "Timer manager '{}' cannot rename timer '{}' to taken name '{}'",
+                        self.name,
+                        old,
+                        new
+                    );
+                    self.send_event(TimerEvent::TimerRejected {
+                        name: new,
+                        reason: RejectReason::AlreadyExists,
+                    });
+                } else if old != new {
+                    self.rename(&old, new);
+                }
+            }
+            TimerCommand::ExtendTimer { name, additional } => match self.timers.remove(&name) {
+                Some(mut entry) => match entry.expires_at.checked_add(additional) {
+                    Some(expires_at) => {
+                        entry.expires_at = expires_at;
+                        let remaining = expires_at.saturating_duration_since(self.now());
+                        self.store(name.clone(), entry);
+                        self.notify_watchers(
+                            &name,
+                            TimerLifecycleEvent::Reset {
+                                duration: remaining,
+                            },
+                        );
+                    }
+                    None => {
+                        log::warn!(
+                            "Timer manager '{}' cannot extend timer '{}' by {:?}",
+                            self.name,
+                            name,
+                            additional
+                        );
+                        // Unchanged, so its expiry heap item is still live
+                        self.timers.insert(name.clone(), entry);
+                        self.send_event(TimerEvent::TimerRejected {
+                            name,
+                            reason: RejectReason::DeadlineOverflow,
+                        });
+                    }
+                },
+                None => {
+                    log::warn!(
+                        "Timer manager '{}' cannot extend timer '{}': not armed",
+                        self.name,
+                        name
+                    );
+                    self.send_event(TimerEvent::TimerNotFound { name });
+                }
+            },
+            TimerCommand::SetRecurringTimer { name, interval } => {
+                if interval < MIN_HEARTBEAT_INTERVAL {
+                    log::warn!(
+                        "Timer manager '{}' rejected recurring timer '{}' with interval {:?}",
+                        self.name,
+                        name,
+                        interval
+                    );
+                    self.send_event(TimerEvent::TimerRejected {
+                        name,
+                        reason: RejectReason::IntervalTooShort,
+                    });
+                } else {
+                    self.insert_entry(name, interval, Some(interval), None);
+                }
+            }
+            TimerCommand::SetTimerWithJitter {
+                name,
+                duration,
+                jitter,
+            } => {
+                let delay = self.random_jitter(jitter);
+                self.insert_timer(name, duration.saturating_add(delay));
+            }
+            TimerCommand::SetTimerWithPriority {
+                name,
+                duration,
+                priority,
+            } => {
+                if self.insert_timer(name.clone(), duration) != Insertion::Rejected {
+                    if let Some(entry) = self.timers.get_mut(&name) {
+                        entry.priority = priority;
+                    }
+                }
+            }
+            TimerCommand::SetTimerEscalating {
+                name,
+                duration,
+                escalate_below,
+            } => {
+                if self.insert_timer(name.clone(), duration) != Insertion::Rejected {
+                    if let Some(entry) = self.timers.get_mut(&name) {
+                        entry.escalate_below = Some(escalate_below);
+                    }
+                }
+            }
+            TimerCommand::SetBatchTracked { batch_id, timers } => {
+                self.set_batch_tracked(batch_id, timers);
+            }
+            TimerCommand::SetTimerConfirmed {
+                name,
+                duration,
+                respond_to,
+            } => {
+                self.insert_timer(name, duration);
+                let _ = respond_to.send(());
+            }
+            TimerCommand::SetAnonymousTimer {
+                duration,
+                respond_to,
+            } => {
+                let name = self.generate_anonymous_name();
+                self.insert_timer(name.clone(), duration);
+                let _ = respond_to.send(name);
+            }
+            TimerCommand::Sleep {
+                duration,
+                respond_to,
+                wake,
+            } => {
+                let name = self.generate_anonymous_name();
+                if self.insert_timer(name.clone(), duration) != Insertion::Rejected {
+                    self.sleepers.insert(name.clone(), wake);
+                }
+                let _ = respond_to.send(name);
+            }
+            TimerCommand::SleepNamed {
+                name,
+                duration,
+                wake,
+            } => {
+                if self.insert_timer(name.clone(), duration) != Insertion::Rejected {
+                    self.sleepers.insert(name, wake);
+                }
+            }
+            TimerCommand::Watch { name, events } => {
+                self.watchers.entry(name).or_default().push(events);
+            }
+            TimerCommand::Ping { respond_to } => {
+                let _ = respond_to.send(());
+            }
+            TimerCommand::DurationStats { respond_to } => {
+                let _ = respond_to.send(self.duration_stats());
+            }
+            TimerCommand::DrainTimers { respond_to } => {
+                let drained = self.held_timers();
+                self.cancel_all();
+                let _ = respond_to.send(drained);
+            }
+            TimerCommand::ListTimers { respond_to } => {
+                let names = self
+                    .remaining_timers()
+                    .into_iter()
+                    .map(|(name, _)| name)
+                    .collect();
+                let _ = respond_to.send(names);
+            }
+            TimerCommand::Snapshot { respond_to } => {
+                let _ = respond_to.send(self.remaining_timers());
+            }
+            TimerCommand::Count { respond_to } => {
+                let _ = respond_to.send(self.timers.len());
+            }
+            TimerCommand::Contains { name, respond_to } => {
+                let _ = respond_to.send(self.timers.contains_key(&name));
+            }
+            TimerCommand::Query { name, respond_to } => {
+                let now = self.now();
+                let remaining = self
+                    .timers
+                    .get(&name)
+                    .map(|entry| entry.expires_at.saturating_duration_since(now));
+                let _ = respond_to.send(remaining);
+            }
+            TimerCommand::FiringWithin { window, respond_to } => {
+                let mut timers = self.remaining_timers();
+                timers.retain(|(_, remaining)| *remaining <= window);
+                let _ = respond_to.send(timers);
+            }
+            TimerCommand::Stats { respond_to } => {
+                let _ = respond_to.send(TimerStats {
+                    active_timers: self.timers.len(),
+                    total_fired: self.total_fired,
+                    dropped_events: self.dropped_events,
+                });
+            }
+            TimerCommand::GetMetrics { respond_to } => {
+                let _ = respond_to.send(self.metrics);
+            }
+            TimerCommand::TakeMetrics { respond_to } => {
+                let _ = respond_to.send(std::mem::take(&mut self.metrics));
+            }
+            TimerCommand::QueryConfig { respond_to } => {
+                let _ = respond_to.send(self.effective_config());
+            }
+            TimerCommand::FullDiagnostics { respond_to } => {
+                let _ = respond_to.send(Diagnostics {
+                    metrics: self.metrics,
+                    timers: self.remaining_timers(),
+                    config: self.effective_config(),
+                    uptime: self.now().saturating_duration_since(self.started_at),
+                });
+            }
+            #[cfg(any(test, feature = "testing"))]
+            TimerCommand::InjectExpiry { name } => {
+                let event = TimerEvent::TimerExpired {
+                    name: name.clone(),
+                    payload: None,
+                    remaining_timers: self.timers.len(),
+                    iteration: 0,
+                    armed_for: Duration::ZERO,
+                    since_last_reset: Duration::ZERO,
+                    scheduled: Duration::ZERO,
+                };
+                self.deliver_expiration(&name, event).await;
+            }
+        }
+        self.metrics.commands_processed += 1;
+        shutdown
+    }
+
+    /// Cancel every timer
+    fn cancel_all(&mut self) {
+        // Pending dependents are discarded rather than armed by the cancellations
+        self.dependents.clear();
+        let names: Vec<String> = self
+            .timers
+            .keys()
+            .chain(self.paused.keys())
+            .chain(self.grace_cancelled.keys())
+            .cloned()
+            .collect();
+        for name in names {
+            self.remove_cancelled(&name);
+        }
+    }
+
+    /// Cancel each of `names` that is set, returning how many were
+    fn cancel_named(&mut self, names: Vec<String>) -> usize {
+        let mut cancelled = 0;
+        for name in names {
+            if self.remove_cancelled(&name) {
+                cancelled += 1;
+                if self.lifecycle_events {
+                    self.send_event(TimerEvent::TimerCancelled { name });
+                }
+            }
+        }
+        cancelled
+    }
+
+    /// Remove a timer because it was cancelled, returning whether it existed
+    ///
+    /// Also completes a graceful cancellation of `name` that is still pending.
+    fn remove_cancelled(&mut self, name: &str) -> bool {
+        // Cancelling a dependent that is still waiting discards it
+        for waiting in self.dependents.values_mut() {
+            waiting.retain(|dependent| dependent.name != name);
+        }
+        self.dependents.retain(|_, waiting| !waiting.is_empty());
+
+        if self.timers.remove(name).is_none()
+            && self.paused.remove(name).is_none()
+            && self.grace_cancelled.remove(name).is_none()
+        {
+            return false;
+        }
+        log::debug!("Timer '{}' cancelled in manager '{}'", name, self.name);
+        self.sleepers.remove(name);
+        self.metrics.timers_cancelled += 1;
+        self.track_removed(name);
+        self.notify_watchers(name, TimerLifecycleEvent::Cancelled);
+        self.resolve_dependents(name, false);
+        true
+    }
+
+    /// Undo a graceful cancellation still within its grace period, returning the
+    /// time the restored timer has left
+    ///
+    /// A pending cancellation whose grace period is over takes effect instead.
+    fn restore_grace_cancelled(&mut self, name: &str) -> Option<Duration> {
+        let now = self.now();
+        if self.grace_cancelled.get(name)?.until <= now {
+            self.remove_cancelled(name);
+            return None;
+        }
+        let GraceCancelled {
+            mut entry,
+            paused_remaining,
+            ..
+        } = self.grace_cancelled.remove(name)?;
+        log::debug!(
+            "Timer '{}' restored within its grace period in manager '{}'",
+            name,
+            self.name
+        );
+        self.idle_since = None;
+
+        // A fresh sequence number leaves the deadline's old heap item stale
+        entry.seq = self.next_seq;
+        self.next_seq += 1;
+        match paused_remaining {
+            Some(remaining) => {
+                self.paused.insert(name.to_string(), (entry, remaining));
+                Some(remaining)
+            }
+            None => {
+                let remaining = entry.expires_at.saturating_duration_since(now);
+                self.store(name.to_string(), entry);
+                Some(remaining)
+            }
+        }
+    }
+
+    /// Complete the graceful cancellations whose grace period is over
+    fn finish_grace_periods(&mut self, now: Instant) {
+        let finished: Vec<String> = self
+            .grace_cancelled
+            .iter()
+            .filter(|(_, cancelled)| cancelled.until <= now)
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in finished {
+            self.remove_cancelled(&name);
+        }
+    }
+
+    /// Arm the timers waiting on `name` now that it has fired or been cancelled
+    fn resolve_dependents(&mut self, name: &str, fired: bool) {
+        let Some(waiting) = self.dependents.remove(name) else {
+            return;
+        };
+        for dependent in waiting {
+            if fired || dependent.arm_on_cancel {
+                self.insert_timer(dependent.name, dependent.duration);
+            }
+        }
+    }
+
+    /// Compute remaining-duration statistics in a single pass over the timers
+    fn duration_stats(&self) -> Option<DurationStats> {
+        let now = self.now();
+        let mut remaining = self
+            .timers
+            .values()
+            .map(|entry| entry.expires_at.saturating_duration_since(now));
+
+        let first = remaining.next()?;
+        let mut stats = DurationStats {
+            count: 1,
+            min_remaining: first,
+            max_remaining: first,
+            mean_remaining: Duration::ZERO,
+        };
+        let mut total_nanos = first.as_nanos();
+        for duration in remaining {
+            stats.count += 1;
+            stats.min_remaining = stats.min_remaining.min(duration);
+            stats.max_remaining = stats.max_remaining.max(duration);
+            total_nanos += duration.as_nanos();
+        }
+        stats.mean_remaining = Duration::from_nanos((total_nanos / stats.count as u128) as u64);
+        Some(stats)
+    }
+
+    /// Apply the shutdown policy, returning whether the manager should stop now
+    async fn begin_shutdown(&mut self) -> bool {
+        match self.shutdown_policy {
+            ShutdownPolicy::Discard => true,
+            ShutdownPolicy::FireAll => {
+                self.fire_all().await;
+                true
+            }
+            ShutdownPolicy::DrainExpired => {
+                self.fire_timers_due_by(self.now()).await;
+                self.flush_overflow();
+                true
+            }
+            ShutdownPolicy::WaitDrain { timeout } => {
+                if self.timers.is_empty() {
+                    return true;
+                }
+                if self.drain_deadline.is_none() {
+                    log::info!(
+                        "Timer manager '{}' waiting up to {:?} for {} timer(s) to drain",
+                        self.name,
+                        timeout,
+                        self.timers.len()
+                    );
+                    self.drain_deadline = Some(tokio_now() + timeout);
+                }
+                false
+            }
+        }
+    }
+
+    /// Whether a `WaitDrain` shutdown has run out of timers or time
+    fn drain_finished(&self) -> bool {
+        self.drain_deadline
+            .is_some_and(|deadline| self.timers.is_empty() || tokio_now() >= deadline)
+    }
+
+    /// Fire every pending timer immediately, still in deadline order
+    async fn fire_all(&mut self) {
+        if let Some(latest) = self.timers.values().map(|entry| entry.expires_at).max() {
+            self.fire_timers_due_by(latest).await;
+        }
+    }
+
+    /// Arm a one-shot timer
+    fn insert_timer(&mut self, name: String, duration: Duration) -> Insertion {
+        self.insert_entry(name, duration, None, None)
+    }
+
+    /// Arm a one-shot (`interval` is `None`) or recurring timer
+    ///
+    /// Setting a paused timer resumes it first, so it counts as a replacement.
+    fn insert_entry(
+        &mut self,
+        name: String,
+        duration: Duration,
+        interval: Option<Duration>,
+        payload: Option<T>,
+    ) -> Insertion {
+        // Setting a gracefully cancelled timer any other way lets the cancellation stand
+        if self.grace_cancelled.contains_key(&name) {
+            self.remove_cancelled(&name);
+        }
+        self.unpause(&name);
+        let is_new = !self.timers.contains_key(&name);
+        if is_new && self.creation_rate_exceeded() {
+            log::warn!(
+                "Timer manager '{}' over its creation rate limit, rejecting timer '{}'",
+                self.name,
+                name
+            );
+            self.send_event(TimerEvent::TimerRejected {
+                name,
+                reason: RejectReason::CreationRateLimit,
+            });
+            return Insertion::Rejected;
+        }
+
+        let at_capacity = self
+            .max_timers
+            .is_some_and(|max_timers| self.timers.len() + self.paused.len() >= max_timers);
+        if at_capacity && !self.timers.contains_key(&name) {
+            let made_room = match self.capacity_policy {
+                CapacityPolicy::Reject => false,
+                CapacityPolicy::EvictFurthest => self.evict_furthest(),
+            };
+            if !made_room {
+                log::warn!(
+                    "Timer manager '{}' at capacity, rejecting timer '{}'",
+                    self.name,
+                    name
+                );
+                self.send_event(TimerEvent::TimerRejected {
+                    name,
+                    reason: RejectReason::AtCapacity,
+                });
+                return Insertion::Rejected;
+            }
+        }
+
+        let now = self.now();
+        let created_at = self.timers.get(&name).map_or(now, |entry| entry.created_at);
+        let entry = TimerEntry {
+            expires_at: now + duration,
+            seq: self.next_seq,
+            interval,
+            iteration: 0,
+            created_at,
+            reset_at: now,
+            priority: 0,
+            escalate_below: None,
+            payload,
+            scheduled: interval.unwrap_or(duration),
+        };
+        self.next_seq += 1;
+        self.metrics.timers_set += 1;
+        self.idle_since = None;
+        log::debug!(
+            "Timer '{}' set for {:?} in manager '{}'",
+            name,
+            duration,
+            self.name
+        );
+        match self.store(name.clone(), entry) {
+            Some(old) => {
+                self.notify_watchers(&name, TimerLifecycleEvent::Reset { duration });
+                Insertion::Replaced {
+                    old_remaining: old.expires_at.saturating_duration_since(now),
+                }
+            }
+            None => {
+                self.count_creation(now);
+                self.track_added(&name);
+                self.notify_watchers(&name, TimerLifecycleEvent::Set { duration });
+                Insertion::Added
+            }
+        }
+    }
+
+    /// Push every deadline back by the time it spent frozen since `paused_at`
+    ///
+    /// A timer set during the freeze starts counting down only now.
+    fn shift_deadlines_since(&mut self, paused_at: Instant) {
+        let now = self.now();
+        for entry in self.timers.values_mut() {
+            let frozen_since = paused_at.max(entry.reset_at);
+            // A deadline too far out to represent is left where it was
+            if let Some(expires_at) = entry
+                .expires_at
+                .checked_add(now.saturating_duration_since(frozen_since))
+            {
+                entry.expires_at = expires_at;
+            }
+        }
+        self.rebuild_expiry_heap();
+    }
+
+    /// Multiply the remaining duration of every active and paused timer by `factor`
+    fn scale_remaining(&mut self, factor: f64) {
+        let scale = |remaining: Duration| {
+            Duration::try_from_secs_f64(remaining.as_secs_f64() * factor).unwrap_or(Duration::MAX)
+        };
+        let now = self.now();
+        for entry in self.timers.values_mut() {
+            // Under `PauseAll`, time left is measured from when the clock froze
+            let from = self
+                .clock_paused_at
+                .map_or(now, |paused_at| paused_at.max(entry.reset_at));
+            let remaining = scale(entry.expires_at.saturating_duration_since(from));
+            // A deadline too far out to represent is left where it was
+            if let Some(expires_at) = from.checked_add(remaining) {
+                entry.expires_at = expires_at;
+            }
+        }
+        for (_, remaining) in self.paused.values_mut() {
+            *remaining = scale(*remaining);
+        }
+        self.rebuild_expiry_heap();
+    }
+
+    /// Re-arm a paused timer with the duration it had left, returning whether it was paused
+    fn unpause(&mut self, name: &str) -> bool {
+        let Some((mut entry, remaining)) = self.paused.remove(name) else {
+            return false;
+        };
+        entry.expires_at = self.now() + remaining;
+        self.store(name.to_string(), entry);
+        true
+    }
+
+    /// Whether the current creation window is already full
+    fn creation_rate_exceeded(&self) -> bool {
+        let (Some((max, window)), Some((start, count))) =
+            (self.creation_rate_limit, self.creation_window)
+        else {
+            return false;
+        };
+        self.now() < start + window && count >= max
+    }
+
+    /// Count a new timer against the creation rate limit
+    fn count_creation(&mut self, now: Instant) {
+        let Some((_, window)) = self.creation_rate_limit else {
+            return;
+        };
+        match &mut self.creation_window {
+            Some((start, count)) if now < *start + window => *count += 1,
+            current => *current = Some((now, 1)),
+        }
+    }
+
+    /// Evict the timer with the latest deadline, returning whether one was evicted
+    fn evict_furthest(&mut self) -> bool {
+        let Some(name) = self
+            .timers
+            .iter()
+            .max_by_key(|(_, entry)| (entry.expires_at, entry.seq))
+            .map(|(name, _)| name.clone())
+        else {
+            return false;
+        };
+        let Some(entry) = self.timers.remove(&name) else {
+            return false;
+        };
+
+        let remaining = entry.expires_at.saturating_duration_since(self.now());
+        log::warn!(
+            "Timer manager '{}' at capacity, evicting timer '{}'",
+            self.name,
+            name
+        );
+        self.sleepers.remove(&name);
+        self.metrics.timers_evicted += 1;
+        self.send_event(TimerEvent::TimerEvicted {
+            name: name.clone(),
+            remaining,
+        });
+        self.track_removed(&name);
+        self.notify_watchers(&name, TimerLifecycleEvent::Evicted);
+        self.resolve_dependents(&name, false);
+        true
+    }
+
+    /// Deliver a lifecycle event to the watchers of `name`, forgetting closed ones
+    fn notify_watchers(&mut self, name: &str, event: TimerLifecycleEvent) {
+        let Some(senders) = self.watchers.get_mut(name) else {
+            return;
+        };
+        senders.retain(|sender| match sender.try_send(event.clone()) {
+            Ok(()) => true,
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                log::warn!(
+                    "Watch channel full, dropping lifecycle event for '{}'",
+                    name
+                );
+                true
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => false,
+        });
+        if senders.is_empty() {
+            self.watchers.remove(name);
+        }
+    }
+
+    /// Update watched group populations for a newly added timer
+    fn track_added(&mut self, name: &str) {
+        let mut became_non_empty = Vec::new();
+        for (prefix, count) in &mut self.groups {
+            if name.starts_with(prefix.as_str()) {
+                *count += 1;
+                if *count == 1 {
+                    became_non_empty.push(prefix.clone());
+                }
+            }
+        }
+        for prefix in became_non_empty {
+            self.send_event(TimerEvent::GroupBecameNonEmpty { prefix });
+        }
+    }
+
+    /// Arm a batch of timers and start tracking their resolution
+    fn set_batch_tracked(&mut self, batch_id: String, timers: Vec<(String, Duration)>) {
+        for (name, duration) in timers {
+            if self.insert_timer(name.clone(), duration) == Insertion::Rejected
+                || self.batch_members.get(&name) == Some(&batch_id)
+            {
+                continue;
+            }
+            // A timer belongs to at most one batch; the latest batch claims it
+            self.leave_batch(&name);
+            *self.batch_pending.entry(batch_id.clone()).or_default() += 1;
+            self.batch_members.insert(name, batch_id.clone());
+        }
+
+        // Nothing to wait for if the batch ended up empty
+        if !self.batch_pending.contains_key(&batch_id) {
+            self.send_event(TimerEvent::BatchComplete { batch_id });
+        }
+    }
+
+    /// Resolve a timer's membership in its tracked batch, completing the batch if it was the last
+    fn leave_batch(&mut self, name: &str) {
+        let Some(batch_id) = self.batch_members.remove(name) else {
+            return;
+        };
+        let Some(pending) = self.batch_pending.get_mut(&batch_id) else {
+            return;
+        };
+        *pending -= 1;
+        if *pending == 0 {
+            self.batch_pending.remove(&batch_id);
+            self.send_event(TimerEvent::BatchComplete { batch_id });
+        }
+    }
+
+    /// Move the timer `old` and its bookkeeping to the free name `new`
+    fn rename(&mut self, old: &str, new: String) {
+        log::debug!(
+            "Timer '{}' renamed to '{}' in manager '{}'",
+            old,
+            new,
+            self.name
+        );
+        if self.grace_cancelled.contains_key(&new) {
+            self.remove_cancelled(&new);
+        }
+        if let Some(entry) = self.timers.remove(old) {
+            self.store(new.clone(), entry);
+        }
+        if let Some(paused) = self.paused.remove(old) {
+            self.paused.insert(new.clone(), paused);
+        }
+        if let Some(wake) = self.sleepers.remove(old) {
+            self.sleepers.insert(new.clone(), wake);
+        }
+        if let Some(waiting) = self.dependents.remove(old) {
+            self.dependents.insert(new.clone(), waiting);
+        }
+
+        // The batch stays pending, while watched groups see a removal and an addition
+        let batch_id = self.batch_members.remove(old);
+        self.track_removed(old);
+        if let Some(batch_id) = batch_id {
+            self.batch_members.insert(new.clone(), batch_id);
+        }
+        self.track_added(&new);
+    }
+
+    /// Update watched group populations and batch membership for a removed timer
+    fn track_removed(&mut self, name: &str) {
+        self.leave_batch(name);
+        self.last_set.remove(name);
+
+        let mut became_empty = Vec::new();
+        for (prefix, count) in &mut self.groups {
+            if name.starts_with(prefix.as_str()) {
+                *count -= 1;
+                if *count == 0 {
+                    became_empty.push(prefix.clone());
+                }
+            }
+        }
+        for prefix in became_empty {
+            self.send_event(TimerEvent::GroupBecameEmpty { prefix });
+        }
+    }
+
+    /// Whether a set of `name` for `duration` repeats the previous one within the
+    /// coalescing window
+    fn coalesced(&self, name: &str, duration: Duration) -> bool {
+        let (Some(window), Some(&(set_at, last_duration))) =
+            (self.coalesce_window, self.last_set.get(name))
+        else {
+            return false;
+        };
+        last_duration == duration
+            && self.timers.contains_key(name)
+            && self.now().saturating_duration_since(set_at) < window
+    }
+
+    /// Draw a delay uniformly from `[0, jitter]` (splitmix64)
+    fn random_jitter(&mut self, jitter: Duration) -> Duration {
+        self.rng_state = self.rng_state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng_state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+
+        let nanos = u128::from(z) % (jitter.as_nanos() + 1);
+        Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+    }
+
+    /// Generate a timer name of the form `anon:{n}` that is not currently in use
+    fn generate_anonymous_name(&mut self) -> String {
+        loop {
+            let name = format!("anon:{}", self.next_anon_id);
+            self.next_anon_id += 1;
+            if !self.timers.contains_key(&name) {
+                return name;
+            }
+        }
+    }
+
+    /// Detect an anomalous gap since the previous heartbeat and apply the configured policy
+    async fn check_clock_jump(&mut self) {
+        let now = tokio_now();
+        let last_tick = self.last_tick.replace(now);
+
+        let (Some((threshold, policy)), Some(last_tick)) = (self.clock_jump, last_tick) else {
+            return;
+        };
+
+        let gap = now.duration_since(last_tick);
+        if gap <= threshold {
+            return;
+        }
+
+        log::warn!(
+            "Timer manager '{}' detected a clock jump of {:?} between heartbeats",
+            self.name,
+            gap
+        );
+        self.send_event(TimerEvent::ClockJump { gap });
+
+        match policy {
+            ClockJumpPolicy::Notify => {}
+            ClockJumpPolicy::Rephase => {
+                let lost = gap.saturating_sub(self.heartbeat_interval);
+                for entry in self.timers.values_mut() {
+                    // A deadline too far out to represent is left where it was
+                    if let Some(expires_at) = entry.expires_at.checked_add(lost) {
+                        entry.expires_at = expires_at;
+                    }
+                }
+                self.rebuild_expiry_heap();
+            }
+            ClockJumpPolicy::FireAll => self.fire_all().await,
+        }
+    }
+
+    /// Emit `HeartbeatLagged` if the sweep begun at `sweep_started` ran too long
+    fn check_heartbeat_lag(&self, sweep_started: Instant) {
+        let Some(threshold) = self.heartbeat_lag else {
+            return;
+        };
+        let elapsed = tokio_now().saturating_duration_since(sweep_started);
+        if elapsed <= threshold {
+            return;
+        }
+
+        let skipped = elapsed.as_nanos() / self.heartbeat_interval.as_nanos();
+        let skipped = u32::try_from(skipped).unwrap_or(u32::MAX);
+        log::warn!(
+            "Timer manager '{}' sweep took {:?}, skipping {} heartbeats",
+            self.name,
+            elapsed,
+            skipped
+        );
+        self.send_event(TimerEvent::HeartbeatLagged { skipped, elapsed });
+    }
+
+    /// Send a non-expiry event, dropping it if the event channel is unavailable
+    fn send_event(&self, event: TimerEvent<T>) {
+        if let Err(e) = self.event_tx.try_send(event) {
+            log::warn!(
+                "Timer manager '{}' could not deliver event: {}",
+                self.name,
+                e
+            );
+        }
+    }
+
+    /// Check for expired timers and fire them
+    ///
+    /// Timers expiring in the same sweep fire in deadline order; equal deadlines
+    /// fire in the order the timers were set.
+    ///
+    /// With near-term sweeps enabled, every `full_sweep_every`-th tick also
+    /// rebuilds the expiry heap first.
+    async fn check_expired_timers(&mut self) {
+        if let Some(full_sweep_every) = self.full_sweep_every {
+            if self.ticks_until_full_sweep == 0 {
+                self.ticks_until_full_sweep = full_sweep_every - 1;
+                self.rebuild_expiry_heap();
+            } else {
+                self.ticks_until_full_sweep -= 1;
+            }
+        }
+        let now = self.now();
+        self.finish_grace_periods(now);
+        self.fire_timers_due_by(now).await;
+    }
+
+    /// Deliver an expiration event under the overflow policy, returning whether
+    /// the sweep should stop
+    async fn deliver_expiration(&mut self, name: &str, event: TimerEvent<T>) -> bool {
+        let sent = match self.overflow_policy {
+            // Use try_send to avoid blocking if event channel is full
+            EventOverflowPolicy::Drop => self.event_tx.try_send(event),
+            EventOverflowPolicy::Block => self.event_tx.send(event).await,
+            EventOverflowPolicy::DropOldest => match self.event_tx.capacity() {
+                Some(capacity) => return self.hold_back_expiration(name, event, capacity),
+                None => self.event_tx.try_send(event),
+            },
+        };
+        let Err(e) = sent else {
+            self.count_delivered();
+            return false;
+        };
+        self.undeliverable(name, e)
+    }
+
+    /// Queue an expiration behind those already held back and deliver as many as
+    /// fit, dropping the oldest beyond `capacity`; returns whether the channel closed
+    fn hold_back_expiration(&mut self, name: &str, event: TimerEvent<T>, capacity: usize) -> bool {
+        self.overflow.push_back((name.to_string(), event));
+        let mut closed = self.flush_overflow();
+        if self.overflow.len() > capacity {
+            if let Some((oldest_name, oldest)) = self.overflow.pop_front() {
+                closed |= self.undeliverable(&oldest_name, mpsc::error::TrySendError::Full(oldest));
+            }
+        }
+        closed
+    }
+
+    /// Deliver held-back expirations in order until the channel is full, returning
+    /// whether it closed
+    fn flush_overflow(&mut self) -> bool {
+        let mut closed = false;
+        while let Some((name, event)) = self.overflow.pop_front() {
+            match self.event_tx.try_send(event) {
+                Ok(()) => self.count_delivered(),
+                Err(mpsc::error::TrySendError::Full(event)) => {
+                    self.overflow.push_front((name, event));
+                    break;
+                }
+                Err(e) => closed |= self.undeliverable(&name, e),
+            }
+        }
+        closed
+    }
+
+    /// Count an expiration that reached the event channel
+    fn count_delivered(&mut self) {
+        self.metrics.timers_fired += 1;
+        self.total_fired += 1;
+    }
+
+    /// Dead-letter or drop an expiration the event channel refused, returning
+    /// whether the sweep should stop
+    fn undeliverable(&mut self, name: &str, e: mpsc::error::TrySendError<TimerEvent<T>>) -> bool {
+        let mut closed = matches!(e, mpsc::error::TrySendError::Closed(_));
+        if closed && self.overflow_policy == EventOverflowPolicy::Block {
+            closed = self.on_closed_during_send();
+        }
+        let dead_lettered = self
+            .dead_letter_tx
+            .as_ref()
+            .is_some_and(|tx| tx.try_send(e.into_inner()).is_ok());
+
+        if dead_lettered {
+            self.metrics.events_dead_lettered += 1;
+        } else {
+            self.metrics.events_dropped += 1;
+            self.dropped_events += 1;
+            if closed {
+                log::warn!(
+                    "Event channel closed, cannot send timer expiration for '{}'",
+                    name
+                );
+            } else if let Some(on_dropped) = &self.on_dropped {
+                on_dropped(name);
+            } else {
+                log::warn!(
+                    "Event channel full, dropping timer expiration for '{}'",
+                    name
+                );
+            }
+        }
+        closed
+    }
+
+    /// Apply the `ClosedDuringSend` policy, returning whether to stop the sweep
+    fn on_closed_during_send(&mut self) -> bool {
+        log::warn!(
+            "Event channel of timer manager '{}' closed during a blocking send, applying {:?}",
+            self.name,
+            self.closed_during_send
+        );
+        match self.closed_during_send {
+            ClosedDuringSend::Shutdown => {
+                self.stop_requested = true;
+                true
+            }
+            ClosedDuringSend::SwitchToDrop => {
+                self.overflow_policy = EventOverflowPolicy::Drop;
+                true
+            }
+            ClosedDuringSend::DeadLetter => false,
+        }
+    }
+
+    /// Fire every timer whose deadline is at or before `now`
+    ///
+    /// Only the due items are popped off the expiry heap, so the cost does not
+    /// depend on how many timers are still pending.
+    async fn fire_timers_due_by(&mut self, now: Instant) {
+        let mut expired_timers = Vec::new();
+        while let Some(Reverse((expires_at, _, _))) = self.expiry_heap.peek() {
+            if *expires_at > now {
+                break;
+            }
+            let Some(Reverse((expires_at, seq, name))) = self.expiry_heap.pop() else {
+                break;
+            };
+            if self.is_live(expires_at, seq, &name) {
+                expired_timers.push(name);
+            }
+        }
+        self.fire_expired(expired_timers).await;
+    }
+
+    /// Store a timer and index its deadline, returning the entry it replaced
+    fn store(&mut self, name: String, entry: TimerEntry<T>) -> Option<TimerEntry<T>> {
+        self.expiry_heap
+            .push(Reverse((entry.expires_at, entry.seq, name.clone())));
+        let replaced = self.timers.insert(name, entry);
+        if self.expiry_heap.len() > EXPIRY_HEAP_COMPACT_FACTOR * self.timers.len().max(16) {
+            self.rebuild_expiry_heap();
+        }
+        replaced
+    }
+
+    /// Whether an expiry heap item still describes the stored timer
+    fn is_live(&self, expires_at: Instant, seq: u64, name: &str) -> bool {
+        self.timers
+            .get(name)
+            .is_some_and(|entry| entry.expires_at == expires_at && entry.seq == seq)
+    }
+
+    /// Rebuild the expiry heap from the stored timers, dropping stale items
+    fn rebuild_expiry_heap(&mut self) {
+        self.expiry_heap = self
+            .timers
+            .iter()
+            .map(|(name, entry)| Reverse((entry.expires_at, entry.seq, name.clone())))
+            .collect();
+    }
+
+    /// Fire the named timers, highest effective priority first, then in deadline
+    /// order
+    ///
+    /// Escalation is judged against the current time rather than the sweep
+    /// horizon, so when `FireAll` fires timers early only those already within
+    /// their escalation threshold are boosted. Each name emits at most one
+    /// expiration per sweep, even if it was queued for expiry more than once.
+    async fn fire_expired(&mut self, mut expired_timers: Vec<String>) {
+        let now = self.now();
+        expired_timers.sort_unstable_by_key(|name| {
+            self.timers.get(name).map(|entry| {
+                (
+                    std::cmp::Reverse(entry.effective_priority(now)),
+                    entry.expires_at,
+                    entry.seq,
+                )
+            })
+        });
+
+        // Process expired timers
+        let mut fired = HashSet::with_capacity(expired_timers.len());
+        let mut expired_timers = expired_timers.into_iter();
+        while let Some(name) = expired_timers.next() {
+            if !fired.insert(name.clone()) {
+                continue;
+            }
+
+            // Remove from storage, re-arming recurring timers from their previous deadline
+            let Some(mut entry) = self.timers.remove(&name) else {
+                continue;
+            };
+            let fired_at = self.now();
+            let next_deadline = entry
+                .interval
+                .and_then(|interval| next_recurrence(entry.expires_at, interval, fired_at));
+            let recurring = match next_deadline {
+                Some(expires_at) => {
+                    entry.iteration += 1;
+                    entry.expires_at = expires_at;
+                    self.store(name.clone(), entry.clone());
+                    true
+                }
+                None => false,
+            };
+
+            // Send expiration event
+            if let Some(level) = self.fire_log_level.to_level() {
+                log_at!(level, "Timer '{}' expired in manager '{}'", name, self.name);
+            }
+
+            let event = TimerEvent::TimerExpired {
+                name: name.clone(),
+                payload: entry.payload,
+                remaining_timers: self.timers.len(),
+                iteration: if recurring { entry.iteration } else { 0 },
+                armed_for: fired_at.saturating_duration_since(entry.created_at),
+                since_last_reset: fired_at.saturating_duration_since(entry.reset_at),
+                scheduled: entry.scheduled,
+            };
+            let closed = match self.sleepers.remove(&name) {
+                Some(wake) => {
+                    let _ = wake.send(());
+                    self.metrics.timers_fired += 1;
+                    self.total_fired += 1;
+                    false
+                }
+                None => self.deliver_expiration(&name, event).await,
+            };
+
+            if !recurring {
+                self.track_removed(&name);
+            }
+            self.notify_watchers(&name, TimerLifecycleEvent::Expired);
+            self.resolve_dependents(&name, true);
+            if closed {
+                // The rest were popped off the expiry heap; index them again so a
+                // later sweep retries them
+                for name in expired_timers {
+                    if let Some(entry) = self.timers.get(&name) {
+                        self.expiry_heap
+                            .push(Reverse((entry.expires_at, entry.seq, name)));
+                    }
+                }
+                break;
+            }
+        }
+    }
+}
+
+impl<T: Clone + Send + 'static> TimerManagerBuilder<T> {
+    /// Create a builder with default settings: 10ms heartbeat, buffers of 100
+    pub fn new(name: impl Into<String>) -> Self {
+        TimerManagerBuilder {
+            name: name.into(),
+            heartbeat_interval: Duration::from_millis(10),
+            command_buffer_size: 100,
+            event_buffer_size: 100,
+            cancel_token: CancellationToken::new(),
+            clock_jump: None,
+            heartbeat_lag: None,
+            shutdown_policy: ShutdownPolicy::default(),
+            initial_capacity: 0,
+            groups: Vec::new(),
+            preload: Vec::new(),
+            command_rate_limit: None,
+            on_dropped: None,
+            adaptive_heartbeat: None,
+            now_fn: None,
+            fire_log_level: ::log::LevelFilter::Debug,
+            max_timers: None,
+            capacity_policy: CapacityPolicy::default(),
+            idle_shutdown: None,
+            dead_letter_tx: None,
+            recorder: None,
+            full_sweep_every: None,
+            overflow_policy: EventOverflowPolicy::default(),
+            closed_during_send: ClosedDuringSend::default(),
+            lifecycle_events: false,
+            coalesce_window: None,
+            rng_seed: None,
+            creation_rate_limit: None,
+        }
+    }
+
+    /// Set the instance name used in log messages
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Set how often to check for expired timers
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is shorter than [`MIN_HEARTBEAT_INTERVAL`].
+    pub fn heartbeat(mut self, interval: Duration) -> Self {
+        assert!(
+            interval >= MIN_HEARTBEAT_INTERVAL,
+            "heartbeat interval {:?} is below the minimum of {:?}",
+            interval,
+            MIN_HEARTBEAT_INTERVAL
+        );
+        self.heartbeat_interval = interval;
+        self
+    }
+
+    /// Set the size of the command channel buffer
+    pub fn command_buffer(mut self, size: usize) -> Self {
+        self.command_buffer_size = size;
+        self
+    }
+
+    /// Set the size of the event channel buffer
+    pub fn event_buffer(mut self, size: usize) -> Self {
+        self.event_buffer_size = size;
+        self
+    }
+
+    /// Set the cancellation token used for graceful shutdown
+    ///
+    /// Commands queued when the token fires are still processed; see [`TimerManager::run`].
+    pub fn cancel_token(mut self, cancel_token: CancellationToken) -> Self {
+        self.cancel_token = cancel_token;
+        self
+    }
+
+    /// Enable detection of heartbeat gaps longer than `threshold`
+    ///
+    /// A gap well beyond the heartbeat interval usually means the host was
+    /// suspended or the runtime was starved. When one is seen the manager emits
+    /// `TimerEvent::ClockJump` and then applies `policy` to the pending timers.
+    pub fn clock_jump_detection(mut self, threshold: Duration, policy: ClockJumpPolicy) -> Self {
+        self.clock_jump = Some((threshold, policy));
+        self
+    }
+
+    /// Emit `TimerEvent::HeartbeatLagged` whenever a sweep takes longer than `threshold`
+    ///
+    /// A sweep that outlasts the heartbeat interval makes the heartbeat skip
+    /// ticks, e.g. while a slow consumer holds up a `Block` send.
+    pub fn heartbeat_lag_detection(mut self, threshold: Duration) -> Self {
+        self.heartbeat_lag = Some(threshold);
+        self
+    }
+
+    /// Set how pending timers are handled when `Shutdown` is received
+    ///
+    /// Defaults to `ShutdownPolicy::Discard`.
+    pub fn shutdown_policy(mut self, policy: ShutdownPolicy) -> Self {
+        self.shutdown_policy = policy;
+        self
+    }
+
+    /// Pre-allocate room for `capacity` timers
+    ///
+    /// This is a sizing hint to avoid rehashing while the timer table fills up,
+    /// not a limit on the number of timers.
+    pub fn initial_capacity(mut self, capacity: usize) -> Self {
+        self.initial_capacity = capacity;
+        self
+    }
+
+    /// Watch the group of timers whose names start with `prefix`
+    ///
+    /// The manager emits `TimerEvent::GroupBecameNonEmpty` when the first timer in
+    /// the group is set and `TimerEvent::GroupBecameEmpty` when the last one fires
+    /// or is cancelled. Replacing an existing timer does not change the population.
+    /// May be called several times to watch several prefixes.
+    pub fn watch_group(mut self, prefix: impl Into<String>) -> Self {
+        self.groups.push(prefix.into());
+        self
+    }
+
+    /// Arm these timers when the manager starts running
+    ///
+    /// Durations count from the start of [`TimerManager::run`].
+    pub fn preload(mut self, timers: Vec<(String, Duration)>) -> Self {
+        self.preload.extend(timers);
+        self
+    }
+
+    /// Process at most one command per `min_spacing`
+    ///
+    /// Commands arriving faster are held in the command channel, which applies
+    /// backpressure to producers once it fills. This adds up to `min_spacing` of
+    /// latency to every command, and timer sweeps wait while a command is held.
+    pub fn command_rate_limit(mut self, min_spacing: Duration) -> Self {
+        self.command_rate_limit = Some(min_spacing);
+        self
+    }
+
+    /// Call `on_dropped` with the timer name whenever an expiration is dropped
+    /// because the event channel is full
+    ///
+    /// Replaces the default warning log. Dropped expirations are still counted in
+    /// `TimerMetrics::events_dropped`. The callback runs inside the manager task,
+    /// so it should return quickly.
+    pub fn on_dropped(mut self, on_dropped: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.on_dropped = Some(Box::new(on_dropped));
+        self
+    }
+
+    /// Replace the fixed heartbeat with one that adapts to the nearest deadline
+    ///
+    /// After every sweep (and whenever a command brings a deadline closer) the next
+    /// tick is scheduled after half of the nearest timer's remaining time, clamped
+    /// to `[min, max]`. With no timers pending the manager ticks every `max`.
+    /// A small `min` improves precision for near-term timers at the cost of more
+    /// wakeups as deadlines approach; a large `max` saves CPU while every timer
+    /// is far off. With clock-jump detection enabled, `max` is lowered to half the
+    /// jump threshold (but not below `min`), so regular ticks never count as a jump.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is shorter than [`MIN_HEARTBEAT_INTERVAL`] or longer than `max`.
+    pub fn adaptive_heartbeat(mut self, min: Duration, max: Duration) -> Self {
+        assert!(
+            min >= MIN_HEARTBEAT_INTERVAL,
+            "adaptive heartbeat minimum {:?} is below the minimum of {:?}",
+            min,
+            MIN_HEARTBEAT_INTERVAL
+        );
+        assert!(
+            min <= max,
+            "adaptive heartbeat minimum {:?} exceeds maximum {:?}",
+            min,
+            max
+        );
+        self.adaptive_heartbeat = Some((min, max));
+        self
+    }
+
+    /// Use `now_fn` instead of Tokio's clock as the current time for timer deadlines
+    ///
+    /// The default already follows `tokio::time::pause` and `advance`, which is
+    /// usually all a test needs. Only deadline arithmetic is affected: the
+    /// heartbeat itself, clock-jump detection, command throttling and the
+    /// `WaitDrain` timeout keep using Tokio's clock, so a timer whose virtual
+    /// deadline has passed fires on the next heartbeat.
+    pub fn now_fn(mut self, now_fn: impl Fn() -> Instant + Send + Sync + 'static) -> Self {
+        self.now_fn = Some(Arc::new(now_fn));
+        self
+    }
+
+    /// Set the level at which each timer expiration is logged
+    ///
+    /// Defaults to `Debug`; `LevelFilter::Off` suppresses the log line entirely.
+    /// The logger's own filter still applies on top of this.
+    pub fn fire_log_level(mut self, level: ::log::LevelFilter) -> Self {
+        self.fire_log_level = level;
+        self
+    }
+
+    /// Limit the number of active timers
+    ///
+    /// What happens to a new timer beyond the limit is set by `capacity_policy`;
+    /// re-setting an existing name always succeeds. Unbounded by default.
+    pub fn max_timers(mut self, max_timers: usize) -> Self {
+        self.max_timers = Some(max_timers);
+        self
+    }
+
+    /// Set what happens when a new timer would exceed `max_timers`
+    ///
+    /// Defaults to `CapacityPolicy::Reject`.
+    pub fn capacity_policy(mut self, policy: CapacityPolicy) -> Self {
+        self.capacity_policy = policy;
+        self
+    }
+
+    /// Stop the manager once it has had no active timers for `timeout`
+    ///
+    /// The idle clock starts when the manager runs and restarts whenever a timer is
+    /// set. On stopping, `TimerEvent::ManagerStopped { reason: StopReason::Idle }` is
+    /// sent. Off by default.
+    pub fn idle_shutdown(mut self, timeout: Duration) -> Self {
+        self.idle_shutdown = Some(timeout);
+        self
+    }
+
+    /// Route expiration events that cannot be delivered to `dead_letter_tx`
+    ///
+    /// Events go here when the event channel is full or closed, instead of being
+    /// dropped. They are only dropped (and counted in `TimerMetrics::events_dropped`)
+    /// if the dead-letter channel cannot take them either. By default undeliverable
+    /// events are dropped.
+    pub fn dead_letter(mut self, dead_letter_tx: mpsc::Sender<TimerEvent<T>>) -> Self {
+        self.dead_letter_tx = Some(dead_letter_tx);
+        self
+    }
+
+    /// Record every processed command that affects timers, with its offset from start
+    ///
+    /// Queries, watches and `CancelIf` are not recorded; other commands are sent
+    /// without their responders. Feed the log to [`TimerManager::replay`] to
+    /// reproduce a session.
+    pub fn record_commands(mut self, sink: mpsc::UnboundedSender<RecordedCommand<T>>) -> Self {
+        self.recorder = Some(sink);
+        self
+    }
+
+    /// Reconcile the expiry heap with the timer table only on every
+    /// `full_sweep_every`-th heartbeat
+    ///
+    /// Every tick checks the near-term end of the expiry heap, which only visits
+    /// the timers already due. A full sweep additionally rebuilds the heap from the
+    /// timer table, dropping the stale items that cancelled and re-set timers leave
+    /// behind, so a population that shrinks without new timers being set releases
+    /// that memory. Without this, stale items are only compacted as timers are set.
+    /// Off by default.
+    pub fn near_term_sweep(mut self, full_sweep_every: u32) -> Self {
+        self.full_sweep_every = Some(full_sweep_every.max(1));
+        self
+    }
+
+    /// Set what happens to expiration events when the event channel is full
+    ///
+    /// Defaults to `EventOverflowPolicy::Drop`.
+    pub fn event_overflow_policy(mut self, policy: EventOverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Set what happens when the event channel closes during a blocking send
+    ///
+    /// Only consulted under `EventOverflowPolicy::Block`. Defaults to
+    /// `ClosedDuringSend::SwitchToDrop`.
+    pub fn closed_during_send(mut self, policy: ClosedDuringSend) -> Self {
+        self.closed_during_send = policy;
+        self
+    }
+
+    /// Emit lifecycle events (`TimerSet`, `TimerReplaced`, `TimerCancelled`,
+    /// `AllTimersCancelled`) on the event channel
+    ///
+    /// Off by default, so consumers only ever see expirations and explicitly
+    /// requested notifications.
+    pub fn lifecycle_events(mut self, enabled: bool) -> Self {
+        self.lifecycle_events = enabled;
+        self
+    }
+
+    /// Ignore a `SetTimer` that repeats the duration of the previous set of the
+    /// same, still active timer within `window`
+    ///
+    /// Debounces noisy producers: the timer keeps the deadline of the first set
+    /// in the burst instead of moving it with every repeat, so it can fire up to
+    /// `window` earlier than the last set asked for. A different duration, a
+    /// payload, or a set after the timer fired or was cancelled is always applied.
+    /// Off by default.
+    pub fn coalesce_sets(mut self, window: Duration) -> Self {
+        self.coalesce_window = Some(window);
+        self
+    }
+
+    /// Seed the generator behind `set_timer_with_jitter`, making the jitter
+    /// reproducible
+    ///
+    /// Without a seed every manager starts from a random one.
+    pub fn rng_seed(mut self, seed: u64) -> Self {
+        self.rng_seed = Some(seed);
+        self
+    }
+
+    /// Allow at most `max` new timers per `window`, rejecting the excess
+    ///
+    /// Windows are fixed: one opens with the first timer created after the previous
+    /// window ended, and counts creations until `window` has elapsed. A rejected
+    /// timer emits `TimerEvent::TimerRejected` with
+    /// `RejectReason::CreationRateLimit`. Re-setting an active timer is not a
+    /// creation and is always allowed. Unlimited by default.
+    pub fn creation_rate_limit(mut self, max: usize, window: Duration) -> Self {
+        self.creation_rate_limit = Some((max, window));
+        self
+    }
+
+    /// Build the TimerManager
+    ///
+    /// Returns (TimerManager, TimerHandle)
+    pub fn build(self) -> (TimerManager<T>, TimerHandle<T>) {
+        let (event_tx, event_rx) = mpsc::channel(self.event_buffer_size);
+        let (manager, commander) = self.build_with_events(EventSender::Channel(event_tx));
+
+        let handle = TimerHandle {
+            commander,
+            event_rx: Some(event_rx),
+        };
+
+        (manager, handle)
+    }
+
+    /// Build a TimerManager that publishes its events on a broadcast channel
+    ///
+    /// Subscribe as often as needed with `broadcast::Sender::subscribe`. Unlike the
+    /// default mpsc channel, a broadcast never applies backpressure: a subscriber
+    /// that falls more than `capacity` events behind loses the oldest ones and gets
+    /// `RecvError::Lagged`, while events sent with no subscriber at all are dropped
+    /// (and handled like a full channel, see `on_dropped` and `dead_letter`). The
+    /// event buffer size and `EventOverflowPolicy::Block` do not apply.
+    ///
+    /// Returns (TimerManager, TimerCommander, broadcast sender)
+    pub fn build_with_broadcast(
+        self,
+        capacity: usize,
+    ) -> (
+        TimerManager<T>,
+        TimerCommander<T>,
+        broadcast::Sender<TimerEvent<T>>,
+    ) {
+        let (event_tx, _) = broadcast::channel(capacity);
+        let (manager, commander) = self.build_with_events(EventSender::Broadcast(event_tx.clone()));
+        (manager, commander, event_tx)
+    }
+
+    /// Build a TimerManager that calls `sink` with each event instead of sending it
+    /// to a channel
+    ///
+    /// The sink runs on the manager task, so it must return quickly and never
+    /// block; the manager itself treats every call as a successful delivery. The
+    /// event buffer size and the overflow policy do not apply.
+    ///
+    /// Returns (TimerManager, TimerCommander)
+    pub fn build_with_sink(
+        self,
+        sink: impl Fn(TimerEvent<T>) + Send + Sync + 'static,
+    ) -> (TimerManager<T>, TimerCommander<T>) {
+        self.build_with_events(EventSender::Sink(Box::new(sink)))
+    }
+
+    /// Build the manager and its commander around the given event sender
+    fn build_with_events(self, event_tx: EventSender<T>) -> (TimerManager<T>, TimerCommander<T>) {
+        let (command_tx, command_rx) = mpsc::channel(self.command_buffer_size);
+        let commander = TimerCommander { command_tx };
+
+        let manager = TimerManager {
+            name: self.name,
+            command_rx,
+            event_tx,
+            timers: HashMap::with_capacity(self.initial_capacity),
+            expiry_heap: BinaryHeap::with_capacity(self.initial_capacity),
+            next_seq: 0,
+            next_anon_id: 0,
+            rng_state: self.rng_seed.unwrap_or_else(|| {
+                use std::hash::{BuildHasher, Hasher};
+                std::collections::hash_map::RandomState::new()
+                    .build_hasher()
+                    .finish()
+            }),
+            heartbeat_interval: self.heartbeat_interval,
+            cancel_token: self.cancel_token,
+            clock_jump: self.clock_jump,
+            last_tick: None,
+            heartbeat_lag: self.heartbeat_lag,
+            metrics: TimerMetrics::default(),
+            shutdown_policy: self.shutdown_policy,
+            drain_deadline: None,
+            groups: self.groups.into_iter().map(|prefix| (prefix, 0)).collect(),
+            preload: self.preload,
+            watchers: HashMap::new(),
+            command_rate_limit: self.command_rate_limit,
+            last_command_at: None,
+            batch_members: HashMap::new(),
+            batch_pending: HashMap::new(),
+            on_dropped: self.on_dropped,
+            adaptive_heartbeat: self.adaptive_heartbeat,
+            dependents: HashMap::new(),
+            now_fn: self.now_fn.unwrap_or_else(|| Arc::new(tokio_now)),
+            fire_log_level: self.fire_log_level,
+            max_timers: self.max_timers,
+            capacity_policy: self.capacity_policy,
+            idle_shutdown: self.idle_shutdown,
+            idle_since: None,
+            dead_letter_tx: self.dead_letter_tx,
+            heartbeat_paused: false,
+            clock_paused_at: None,
+            total_fired: 0,
+            dropped_events: 0,
+            recorder: self.recorder,
+            started_at: tokio_now(),
+            full_sweep_every: self.full_sweep_every,
+            ticks_until_full_sweep: 0,
+            sleepers: HashMap::new(),
+            overflow_policy: self.overflow_policy,
+            closed_during_send: self.closed_during_send,
+            stop_requested: false,
+            heartbeat_changed: false,
+            lifecycle_events: self.lifecycle_events,
+            coalesce_window: self.coalesce_window,
+            last_set: HashMap::new(),
+            grace_cancelled: HashMap::new(),
+            paused: HashMap::new(),
+            overflow: VecDeque::new(),
+            creation_rate_limit: self.creation_rate_limit,
+            creation_window: None,
+        };
+
+        (manager, commander)
+    }
+}
+
+/// Set several named timers with one command
+///
+/// Expands to [`TimerCommander::set_timers`], so the result is awaited once for the
+/// whole batch.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use timer_manager::{set_timers, TimerManager};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let (manager, handle) = TimerManager::builder("example").build();
+///     tokio::spawn(manager.run());
+///
+///     set_timers!(handle,
+///         "a" => Duration::from_secs(1),
+///         "b" => Duration::from_secs(2),
+///         "c" => Duration::from_millis(500),
+///     )
+///     .await?;
+///
+///     handle.shutdown().await?;
+///     Ok(())
+/// }
+/// ```
+#[macro_export]
+macro_rules! set_timers {
+    ($handle:expr, $($name:expr => $duration:expr),+ $(,)?) => {
+        $handle.set_timers(::std::vec![
+            $((::std::string::String::from($name), $duration)),+
+        ])
+    };
+}
+
+impl<T: Clone + Send + 'static> TimerCommander<T> {
+    /// Set a timer (creates new or updates existing)
+    ///
+    /// A zero duration is already due: the manager wakes for it right after
+    /// processing the command and fires it on that pass, through the event channel
+    /// like any other expiration.
+    pub async fn set_timer(&self, name: String, duration: Duration) -> Result<(), TimerError> {
+        self.send(TimerCommand::SetTimer {
+            name,
+            duration,
+            payload: None,
+        })
+        .await
+    }
+
+    /// Set a timer carrying `payload`, which is handed back when it expires
+    pub async fn set_timer_with_payload(
+        &self,
+        name: String,
+        duration: Duration,
+        payload: T,
+    ) -> Result<(), TimerError> {
+        self.send(TimerCommand::SetTimer {
+            name,
+            duration,
+            payload: Some(payload),
+        })
+        .await
+    }
+
+    /// Set a timer that fires at `deadline` (creates new or updates existing)
+    ///
+    /// Unlike `set_timer`, the deadline does not move with the time the command
+    /// spends queued. A deadline that has already passed fires on the next sweep,
+    /// exactly like a zero duration.
+    pub async fn set_timer_at(&self, name: String, deadline: Instant) -> Result<(), TimerError> {
+        self.send(TimerCommand::SetTimerAt { name, deadline }).await
+    }
+
+    /// Set several timers with a single command (each creates new or updates existing)
+    ///
+    /// The whole batch is applied in one step, so no sweep can see only part of
+    /// it, and a single `Result` reports whether the batch was queued. See also the
+    /// [`set_timers!`](crate::set_timers) macro.
+    pub async fn set_timers(&self, timers: Vec<(String, Duration)>) -> Result<(), TimerError> {
+        self.send(TimerCommand::SetTimers { timers }).await
+    }
+
+    /// Set a timer only if no timer called `name` is set yet
+    ///
+    /// An existing timer (even a paused one) keeps its deadline, and
+    /// [`TimerEvent::TimerRejected`] is emitted with [`RejectReason::AlreadyExists`].
+    pub async fn set_timer_if_absent(
+        &self,
+        name: String,
+        duration: Duration,
+    ) -> Result<(), TimerError> {
+        self.send(TimerCommand::SetTimerIfAbsent { name, duration })
+            .await
+    }
+
+    /// Set a timer only if no timer called `name` is set yet (non-blocking)
+    pub fn try_set_timer_if_absent(
+        &self,
+        name: String,
+        duration: Duration,
+    ) -> Result<(), TimerError> {
+        self.try_send(TimerCommand::SetTimerIfAbsent { name, duration })
+    }
+
+    /// Set a timer from synchronous code, blocking while the command channel is full
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from within an asynchronous execution context; use
+    /// [`set_timer`](Self::set_timer) there.
+    pub fn blocking_set_timer(&self, name: String, duration: Duration) -> Result<(), TimerError> {
+        self.blocking_send(TimerCommand::SetTimer {
+            name,
+            duration,
+            payload: None,
+        })
+    }
+
+    /// Set a timer (non-blocking)
+    pub fn try_set_timer(&self, name: String, duration: Duration) -> Result<(), TimerError> {
+        self.try_send(TimerCommand::SetTimer {
+            name,
+            duration,
+            payload: None,
+        })
+    }
+
+    /// Re-arm several existing timers with new durations in one command
+    ///
+    /// All updates are applied together, so no sweep can observe some timers reset
+    /// and others not. Names that are not currently armed are skipped. A recurring
+    /// timer keeps its interval and restarts its iteration count. Returns the names
+    /// that were updated, in the order given.
+    pub async fn reset_timers(
+        &self,
+        updates: Vec<(String, Duration)>,
+    ) -> Result<Vec<String>, TimerError> {
+        self.request(|respond_to| TimerCommand::ResetTimers {
+            updates,
+            respond_to,
+        })
+        .await
+    }
+
+    /// Restart an armed timer so it fires `duration` from now
+    ///
+    /// Unlike `set_timer`, this never creates a timer: if `name` is not armed it
+    /// returns `TimerError::TimerNotFound`.
+    pub async fn reset_timer(&self, name: String, duration: Duration) -> Result<(), TimerError> {
+        let updated = self.reset_timers(vec![(name, duration)]).await?;
+        if updated.is_empty() {
+            return Err(TimerError::TimerNotFound);
+        }
+        Ok(())
+    }
+
+    /// Give the timer `old` the name `new`, keeping its exact deadline
+    ///
+    /// Emits [`TimerEvent::TimerNotFound`] if `old` is not set, or
+    /// [`TimerEvent::TimerRejected`] with [`RejectReason::AlreadyExists`] if `new`
+    /// already is; either way no timer changes.
+    pub async fn rename_timer(&self, old: String, new: String) -> Result<(), TimerError> {
+        self.send(TimerCommand::RenameTimer { old, new }).await
+    }
+
+    /// Push an armed timer's deadline back by `additional`
+    ///
+    /// If the timer is not armed, nothing changes and a `TimerNotFound` event is emitted.
+    /// A deadline pushed too far out to represent leaves the timer unchanged and
+    /// emits `TimerRejected` with `RejectReason::DeadlineOverflow`.
+    pub async fn extend_timer(&self, name: String, additional: Duration) -> Result<(), TimerError> {
+        self.send(TimerCommand::ExtendTimer { name, additional })
+            .await
+    }
+
+    /// Set a recurring timer (creates new or updates existing)
+    ///
+    /// The timer fires every `interval` until cancelled. Each expiration carries its
+    /// 1-based `iteration`. Each deadline is computed from the previous deadline
+    /// rather than from when the timer was swept, so heartbeat latency does not
+    /// accumulate as drift. Periods missed entirely, e.g. while the heartbeat or
+    /// every timer was paused, are skipped: the timer fires once and moves on to
+    /// its next deadline still ahead, and `iteration` counts firings, not periods.
+    /// Intervals below [`MIN_HEARTBEAT_INTERVAL`] are refused
+    /// with [`TimerEvent::TimerRejected`] and [`RejectReason::IntervalTooShort`].
+    pub async fn set_recurring_timer(
+        &self,
+        name: String,
+        interval: Duration,
+    ) -> Result<(), TimerError> {
+        self.send(TimerCommand::SetRecurringTimer { name, interval })
+            .await
+    }
+
+    /// Set a timer that fires at a random point between `duration` and
+    /// `duration + jitter`
+    ///
+    /// Spreads out timers armed together with the same duration, such as
+    /// reconnect backoffs, so they do not all fire in one sweep. The delay is drawn
+    /// by the manager; seed it with [`TimerManagerBuilder::rng_seed`] for
+    /// reproducible runs.
+    pub async fn set_timer_with_jitter(
+        &self,
+        name: String,
+        duration: Duration,
+        jitter: Duration,
+    ) -> Result<(), TimerError> {
+        self.send(TimerCommand::SetTimerWithJitter {
+            name,
+            duration,
+            jitter,
+        })
+        .await
+    }
+
+    /// Set a timer with a base `priority` (plain `set_timer` uses 0)
+    ///
+    /// Among timers expiring in the same sweep, higher priorities are delivered
+    /// first, before earlier deadlines, so they are the last to be dropped when
+    /// the event channel is full. Setting the timer again without a priority
+    /// resets it to 0.
+    pub async fn set_timer_with_priority(
+        &self,
+        name: String,
+        duration: Duration,
+        priority: u8,
+    ) -> Result<(), TimerError> {
+        self.send(TimerCommand::SetTimerWithPriority {
+            name,
+            duration,
+            priority,
+        })
+        .await
+    }
+
+    /// Set a timer that gains priority as its deadline approaches
+    ///
+    /// Once less than `escalate_below` remains, the timer's priority is raised by
+    /// 128 over its base priority (0 for timers set this way), saturating at 255.
+    /// In an ordinary sweep every due timer has no time left, so escalating timers
+    /// are delivered ahead of non-escalated timers of equal base priority that
+    /// expire in the same sweep, and are the last to be dropped when the event
+    /// channel is full. The threshold itself only matters when timers fire before
+    /// their deadline, as under [`ShutdownPolicy::FireAll`] or
+    /// [`ClockJumpPolicy::FireAll`]: then only timers already within
+    /// `escalate_below` of their deadline are boosted.
+    pub async fn set_timer_escalating(
+        &self,
+        name: String,
+        duration: Duration,
+        escalate_below: Duration,
+    ) -> Result<(), TimerError> {
+        self.send(TimerCommand::SetTimerEscalating {
+            name,
+            duration,
+            escalate_below,
+        })
+        .await
+    }
+
+    /// Set a timer, waiting for a reserved slot in the command channel
+    ///
+    /// The command is built only after a slot has been reserved, so it is never
+    /// rejected for lack of capacity and nothing is lost if the await is cancelled
+    /// while waiting. Use this for critical timers (such as watchdogs) instead of
+    /// `try_set_timer`.
+    pub async fn set_timer_reserved(
+        &self,
+        name: String,
+        duration: Duration,
+    ) -> Result<(), TimerError> {
+        let permit = self
+            .command_tx
+            .reserve()
+            .await
+            .map_err(|_| TimerError::ManagerStopped)?;
+        permit.send(TimerCommand::SetTimer {
+            name,
+            duration,
+            payload: None,
+        });
+        Ok(())
+    }
+
+    /// Cancel one timer and set another as a single command
+    ///
+    /// Both changes are applied before the manager checks for expirations, so there
+    /// is no moment where both timers or neither are active. Suited to state
+    /// transitions that replace one state's timeout with the next.
+    pub async fn swap_timer(
+        &self,
+        cancel: String,
+        set_name: String,
+        set_duration: Duration,
+    ) -> Result<(), TimerError> {
+        self.send(TimerCommand::SwapTimer {
+            cancel,
+            set_name,
+            set_duration,
+        })
+        .await
+    }
+
+    /// Cancel a specific timer
+    pub async fn cancel_timer(&self, name: String) -> Result<(), TimerError> {
+        self.send(TimerCommand::CancelTimer { name }).await
+    }
+
+    /// Cancel a timer, but let a `set_timer` within `grace` undo the cancellation
+    ///
+    /// The timer stops right away: it will not fire, and queries no longer report
+    /// it. The rest of the cancellation (`set_after` dependents, `sleep_named`
+    /// waiters, watchers, batches, group counts and metrics) is held back until the
+    /// grace period ends. If `set_timer` for the same name arrives within `grace`,
+    /// the timer comes back as if it had never been cancelled, with its original
+    /// deadline instead of the requested duration, or still paused with the time it
+    /// had left; a deadline that passed meanwhile fires on the next sweep. Other
+    /// ways of setting the timer, a `set_timer` after the grace period, or another
+    /// cancellation complete the cancellation first; otherwise it completes on the
+    /// first heartbeat after the grace period.
+    pub async fn cancel_timer_graceful(
+        &self,
+        name: String,
+        grace: Duration,
+    ) -> Result<(), TimerError> {
+        self.send(TimerCommand::CancelTimerGraceful { name, grace })
+            .await
+    }
+
+    /// Cancel a specific timer (non-blocking)
+    pub fn try_cancel_timer(&self, name: String) -> Result<(), TimerError> {
+        self.try_send(TimerCommand::CancelTimer { name })
+    }
+
+    /// Cancel a specific timer from synchronous code
+    ///
+    /// Panics inside an asynchronous execution context, like
+    /// [`blocking_set_timer`](Self::blocking_set_timer).
+    pub fn blocking_cancel_timer(&self, name: String) -> Result<(), TimerError> {
+        self.blocking_send(TimerCommand::CancelTimer { name })
+    }
+
+    /// Cancel a timer only if `predicate` returns true for its remaining duration
+    ///
+    /// The check and the cancellation happen together inside the manager task.
+    /// Returns whether the timer was cancelled; a missing timer yields `false`.
+    pub async fn cancel_if(
+        &self,
+        name: String,
+        predicate: impl Fn(Duration) -> bool + Send + 'static,
+    ) -> Result<bool, TimerError> {
+        self.request(|respond_to| TimerCommand::CancelIf {
+            name,
+            predicate: RemainingPredicate(Box::new(predicate)),
+            respond_to,
+        })
+        .await
+    }
+
+    /// Stop a timer's countdown until [`resume_timer`](Self::resume_timer)
+    ///
+    /// A paused timer does not fire and is not listed or queried as active, but it
+    /// can still be cancelled, and setting it again resumes and replaces it.
+    /// Pausing a timer that is not armed, or is already paused, does nothing.
+    pub async fn pause_timer(&self, name: String) -> Result<(), TimerError> {
+        self.send(TimerCommand::PauseTimer { name }).await
+    }
+
+    /// Resume a paused timer so it fires after the duration it had left when paused
+    ///
+    /// Resuming a timer that is not paused does nothing.
+    pub async fn resume_timer(&self, name: String) -> Result<(), TimerError> {
+        self.send(TimerCommand::ResumeTimer { name }).await
+    }
+
+    /// Cancel several timers with a single command, returning how many were set
+    ///
+    /// The whole batch is removed in one step, so no sweep can fire some of them
+    /// in between. Names that are not set are skipped.
+    pub async fn cancel_timers(&self, names: Vec<String>) -> Result<usize, TimerError> {
+        self.request(|respond_to| TimerCommand::CancelTimers { names, respond_to })
+            .await
+    }
+
+    /// Cancel every timer whose name starts with `prefix`, returning how many
+    ///
+    /// Suits namespaced names: `cancel_by_prefix("session:123:")` ends everything
+    /// belonging to that session in one step. Paused timers are included.
+    pub async fn cancel_by_prefix(&self, prefix: String) -> Result<usize, TimerError> {
+        self.request(|respond_to| TimerCommand::CancelByPrefix { prefix, respond_to })
+            .await
+    }
+
+    /// Cancel all timers
+    pub async fn cancel_all_timers(&self) -> Result<(), TimerError> {
+        self.send(TimerCommand::CancelAllTimers).await
+    }
+
+    /// Freeze every timer until [`resume_all`](Self::resume_all)
+    ///
+    /// No timer fires while frozen, and timers set meanwhile do not start counting
+    /// down until the thaw. Pausing again while frozen does nothing.
+    pub async fn pause_all(&self) -> Result<(), TimerError> {
+        self.send(TimerCommand::PauseAll).await
+    }
+
+    /// Thaw frozen timers, each keeping the remaining duration it had when frozen
+    pub async fn resume_all(&self) -> Result<(), TimerError> {
+        self.send(TimerCommand::ResumeAll).await
+    }
+
+    /// Suspend the heartbeat until [`resume_heartbeat`](Self::resume_heartbeat)
+    ///
+    /// While paused the manager wakes only for commands, and no timer fires no
+    /// matter how overdue it is. Commands are processed as usual.
+    pub async fn pause_heartbeat(&self) -> Result<(), TimerError> {
+        self.send(TimerCommand::PauseHeartbeat).await
+    }
+
+    /// Resume a paused heartbeat, immediately firing timers that came due meanwhile
+    pub async fn resume_heartbeat(&self) -> Result<(), TimerError> {
+        self.send(TimerCommand::ResumeHeartbeat).await
+    }
+
+    /// Multiply the remaining duration of every timer by `factor`
+    ///
+    /// Speeds up (`factor < 1.0`) or slows down (`factor > 1.0`) everything that
+    /// is pending, e.g. for simulation harnesses; `0.0` fires every active timer
+    /// on the next sweep. Paused timers are scaled too. A negative or non-finite
+    /// factor is refused with [`TimerEvent::ScaleRejected`].
+    pub async fn scale_remaining(&self, factor: f64) -> Result<(), TimerError> {
+        self.send(TimerCommand::ScaleRemaining { factor }).await
+    }
+
+    /// Change the heartbeat interval without restarting the manager
+    ///
+    /// The next heartbeat is one `interval` from when the command is processed.
+    /// An interval below [`MIN_HEARTBEAT_INTERVAL`] is refused with
+    /// [`TimerEvent::HeartbeatRejected`]. Under an adaptive heartbeat this only
+    /// affects the reported config.
+    pub async fn set_heartbeat(&self, interval: Duration) -> Result<(), TimerError> {
+        self.send(TimerCommand::SetHeartbeat { interval }).await
+    }
+
+    /// Cancel all timers (non-blocking)
+    pub fn try_cancel_all_timers(&self) -> Result<(), TimerError> {
+        self.try_send(TimerCommand::CancelAllTimers)
+    }
+
+    /// Cancel all timers from synchronous code
+    ///
+    /// Panics inside an asynchronous execution context, like
+    /// [`blocking_set_timer`](Self::blocking_set_timer).
+    pub fn blocking_cancel_all_timers(&self) -> Result<(), TimerError> {
+        self.blocking_send(TimerCommand::CancelAllTimers)
+    }
+
+    /// Shutdown the timer manager
+    pub async fn shutdown(&self) -> Result<(), TimerError> {
+        self.send(TimerCommand::Shutdown).await
+    }
+
+    /// Shutdown the timer manager and return the names of the timers left unfired
+    ///
+    /// The list is taken once the `ShutdownPolicy` has been applied: with
+    /// `Discard` it holds every active or paused timer, with `FireAll` it is empty.
+    /// Under `WaitDrain` it lists the timers still armed when draining starts.
+    /// Useful for catching timers that were never cancelled before teardown.
+    pub async fn shutdown_and_report(&self) -> Result<Vec<String>, TimerError> {
+        self.request(|respond_to| TimerCommand::ShutdownAndReport { respond_to })
+            .await
+    }
+
+    /// Shutdown the timer manager (non-blocking)
+    pub fn try_shutdown(&self) -> Result<(), TimerError> {
+        self.try_send(TimerCommand::Shutdown)
+    }
+
+    /// Shut down the timer manager from synchronous code
+    ///
+    /// Panics inside an asynchronous execution context, like
+    /// [`blocking_set_timer`](Self::blocking_set_timer).
+    pub fn blocking_shutdown(&self) -> Result<(), TimerError> {
+        self.blocking_send(TimerCommand::Shutdown)
+    }
+
+    /// Arm `name` for `duration` only once the timer `after` fires
+    ///
+    /// The duration counts from the moment `after` fires. If `after` is cancelled
+    /// instead (or is not set when this command is processed), `name` is armed
+    /// only if `arm_on_cancel` is true. A paused prerequisite counts as set, so
+    /// `name` waits for it to be resumed and fire. Cancelling `name` while it is still waiting
+    /// discards it; `cancel_all_timers` discards all waiting dependents.
+    pub async fn set_after(
+        &self,
+        name: String,
+        duration: Duration,
+        after: String,
+        arm_on_cancel: bool,
+    ) -> Result<(), TimerError> {
+        self.send(TimerCommand::SetAfter {
+            name,
+            duration,
+            after,
+            arm_on_cancel,
+        })
+        .await
+    }
+
+    /// Set a batch of timers tracked as one unit
+    ///
+    /// Once every timer in the batch has fired or been cancelled, the manager emits
+    /// `TimerEvent::BatchComplete { batch_id }` after the last timer's own event.
+    /// Re-setting a member keeps it in the batch; adding it to another tracked
+    /// batch moves it there.
+    pub async fn set_batch_tracked(
+        &self,
+        batch_id: String,
+        timers: Vec<(String, Duration)>,
+    ) -> Result<(), TimerError> {
+        self.send(TimerCommand::SetBatchTracked { batch_id, timers })
+            .await
+    }
+
+    /// Set a timer and wait until the manager has armed it
+    ///
+    /// Unlike `set_timer`, which returns once the command is queued, this resolves
+    /// only after the timer is present in the manager's timer table.
+    pub async fn set_timer_confirmed(
+        &self,
+        name: String,
+        duration: Duration,
+    ) -> Result<(), TimerError> {
+        self.request(|respond_to| TimerCommand::SetTimerConfirmed {
+            name,
+            duration,
+            respond_to,
+        })
+        .await
+    }
+
+    /// Set a timer under a unique name chosen by the manager
+    ///
+    /// Returns the generated name (`anon:{n}`), which can be used to cancel the timer
+    /// and appears in its expiration event.
+    pub async fn set_anonymous(&self, duration: Duration) -> Result<String, TimerError> {
+        self.request(|respond_to| TimerCommand::SetAnonymousTimer {
+            duration,
+            respond_to,
+        })
+        .await
+    }
+
+    /// Sleep for `duration` on a timer owned by the manager
+    ///
+    /// Unlike `tokio::time::sleep`, the wait is an anonymous timer of the manager:
+    /// it counts in the metrics and statistics, and firing it completes this future
+    /// instead of emitting a `TimerExpired` event. Dropping the future cancels the
+    /// timer. The future also completes early if the timer is cancelled (e.g. by
+    /// `cancel_all_timers`) or the manager stops.
+    pub fn sleep(&self, duration: Duration) -> impl Future<Output = ()> {
+        let command_tx = self.command_tx.clone();
+        async move {
+            let (respond_to, name_rx) = oneshot::channel();
+            let (wake, wake_rx) = oneshot::channel();
+            let command = TimerCommand::Sleep {
+                duration,
+                respond_to,
+                wake,
+            };
+            if command_tx.send(command).await.is_err() {
+                return;
+            }
+            let Ok(name) = name_rx.await else {
+                return;
+            };
+
+            let mut guard = CancelOnDrop {
+                command_tx,
+                name: Some(name),
+            };
+            let _ = wake_rx.await;
+            guard.name = None;
+        }
+    }
+
+    /// Set the timer `name` and wait until that timer fires
+    ///
+    /// The expiration completes this future instead of being sent as a
+    /// `TimerExpired` event, so nothing has to be filtered out of the event
+    /// channel: every other event stays queued there, in order, for `recv_event`.
+    /// Setting `name` again while waiting only moves the deadline. Dropping the
+    /// future cancels the timer. Returns `TimerError::TimerNotFound` if the timer
+    /// is cancelled before it fires, and `TimerError::ManagerStopped` if the
+    /// manager stops first.
+    pub fn sleep_named(
+        &self,
+        name: String,
+        duration: Duration,
+    ) -> impl Future<Output = Result<(), TimerError>> {
+        let command_tx = self.command_tx.clone();
+        async move {
+            let (wake, wake_rx) = oneshot::channel();
+            let command = TimerCommand::SleepNamed {
+                name: name.clone(),
+                duration,
+                wake,
+            };
+            command_tx
+                .send(command)
+                .await
+                .map_err(|_| TimerError::ManagerStopped)?;
+
+            let mut guard = CancelOnDrop {
+                command_tx,
+                name: Some(name),
+            };
+            let woken = wake_rx.await;
+            guard.name = None;
+            woken.map_err(|_| {
+                if guard.command_tx.is_closed() {
+                    TimerError::ManagerStopped
+                } else {
+                    TimerError::TimerNotFound
+                }
+            })
+        }
+    }
+
+    /// Subscribe to the lifecycle of the timer called `name`
+    ///
+    /// The stream yields every set, reset, expiry and cancellation of that timer
+    /// from the moment the subscription is processed, and ends when the manager
+    /// stops. Events are dropped if the subscriber falls more than a small buffer
+    /// behind.
+    pub async fn watch(
+        &self,
+        name: String,
+    ) -> Result<impl Stream<Item = TimerLifecycleEvent>, TimerError> {
+        let (events, mut event_rx) = mpsc::channel(WATCH_BUFFER_SIZE);
+        self.send(TimerCommand::Watch { name, events }).await?;
+        Ok(futures::stream::poll_fn(move |cx| event_rx.poll_recv(cx)))
+    }
+
+    /// Round-trip a command through the manager task
+    pub async fn ping(&self) -> Result<(), TimerError> {
+        self.request(|respond_to| TimerCommand::Ping { respond_to })
+            .await
+    }
+
+    /// Check whether the manager answers a ping within `timeout`
+    ///
+    /// Returns `false` if the manager has stopped or is alive but too busy to
+    /// process the ping in time (for example when the command queue is backed up).
+    pub async fn ping_timeout(&self, timeout: Duration) -> bool {
+        matches!(tokio::time::timeout(timeout, self.ping()).await, Ok(Ok(())))
+    }
+
+    /// Cancel every timer and return their remaining durations, soonest first
+    ///
+    /// The snapshot and the cancellation happen in one step, so no timer can fire
+    /// in between. Useful for persisting timers and restoring them later via
+    /// `TimerManager::new_with_timers`.
+    pub async fn drain_timers(&self) -> Result<Vec<(String, Duration)>, TimerError> {
+        self.request(|respond_to| TimerCommand::DrainTimers { respond_to })
+            .await
+    }
+
+    /// List the names of all active timers
+    ///
+    /// The snapshot is taken inside the manager task and sorted soonest first;
+    /// timers with equal deadlines are listed in the order they were set.
+    pub async fn list_timers(&self) -> Result<Vec<String>, TimerError> {
+        self.request(|respond_to| TimerCommand::ListTimers { respond_to })
+            .await
+    }
+
+    /// List every active timer with its remaining duration, soonest first
+    ///
+    /// Like `list_timers`, but the remaining durations come in the same round
+    /// trip, all measured against a single reading of the clock.
+    pub async fn snapshot(&self) -> Result<Vec<(String, Duration)>, TimerError> {
+        self.request(|respond_to| TimerCommand::Snapshot { respond_to })
+            .await
+    }
+
+    /// Get the number of active timers, without listing them
+    pub async fn count_timers(&self) -> Result<usize, TimerError> {
+        self.request(|respond_to| TimerCommand::Count { respond_to })
+            .await
+    }
+
+    /// Check whether the timer called `name` is currently armed
+    ///
+    /// A paused timer is not armed.
+    pub async fn contains_timer(&self, name: String) -> Result<bool, TimerError> {
+        self.request(|respond_to| TimerCommand::Contains { name, respond_to })
+            .await
+    }
+
+    /// Get the time left on the timer called `name`
+    ///
+    /// Returns `None` if no such timer is active, and `Some(Duration::ZERO)` if it
+    /// is past due but has not been swept yet.
+    pub async fn get_remaining(&self, name: String) -> Result<Option<Duration>, TimerError> {
+        self.request(|respond_to| TimerCommand::Query { name, respond_to })
+            .await
+    }
+
+    /// List the timers that will fire within `window`, soonest first
+    ///
+    /// Each entry carries the timer's remaining duration.
+    pub async fn firing_within(
+        &self,
+        window: Duration,
+    ) -> Result<Vec<(String, Duration)>, TimerError> {
+        self.request(|respond_to| TimerCommand::FiringWithin { window, respond_to })
+            .await
+    }
+
+    /// Get min, max and mean remaining duration of the active timers
+    ///
+    /// Returns `None` when no timers are active.
+    pub async fn duration_stats(&self) -> Result<Option<DurationStats>, TimerError> {
+        self.request(|respond_to| TimerCommand::DurationStats { respond_to })
+            .await
+    }
+
+    /// Get lifetime totals of active, fired and dropped timers
+    ///
+    /// Unlike [`take_metrics`](Self::take_metrics) counters, these totals never reset.
+    pub async fn stats(&self) -> Result<TimerStats, TimerError> {
+        self.request(|respond_to| TimerCommand::Stats { respond_to })
+            .await
+    }
+
+    /// Get the current metrics
+    pub async fn metrics(&self) -> Result<TimerMetrics, TimerError> {
+        self.request(|respond_to| TimerCommand::GetMetrics { respond_to })
+            .await
+    }
+
+    /// Get the current metrics and reset them to zero
+    ///
+    /// The counters are read and cleared in one step inside the manager task,
+    /// so consecutive calls return disjoint windows of activity.
+    pub async fn take_metrics(&self) -> Result<TimerMetrics, TimerError> {
+        self.request(|respond_to| TimerCommand::TakeMetrics { respond_to })
+            .await
+    }
+
+    /// Emit a synthetic `TimerExpired` event for `name`
+    ///
+    /// The event is delivered like a real expiration (respecting the overflow
+    /// policy, with zero `armed_for` and `iteration`), but the timers are left
+    /// untouched: no timer needs to exist, and an existing one stays armed.
+    /// Meant for exercising event consumers; only available with the `testing`
+    /// feature.
+    #[cfg(any(test, feature = "testing"))]
+    pub async fn inject_expiry(&self, name: String) -> Result<(), TimerError> {
+        self.send(TimerCommand::InjectExpiry { name }).await
+    }
+
+    /// Get the configuration the manager is running with
+    pub async fn config(&self) -> Result<EffectiveConfig, TimerError> {
+        self.request(|respond_to| TimerCommand::QueryConfig { respond_to })
+            .await
+    }
+
+    /// Get metrics, active timers, configuration and uptime as one consistent snapshot
+    pub async fn diagnostics(&self) -> Result<Diagnostics, TimerError> {
+        self.request(|respond_to| TimerCommand::FullDiagnostics { respond_to })
+            .await
+    }
+
+    /// Queue a command, waiting for room in the command channel
+    async fn send(&self, command: TimerCommand<T>) -> Result<(), TimerError> {
+        self.command_tx
+            .send(command)
+            .await
+            .map_err(|_| TimerError::ManagerStopped)
+    }
+
+    /// Queue a command from synchronous code, blocking the thread for room
+    fn blocking_send(&self, command: TimerCommand<T>) -> Result<(), TimerError> {
+        self.command_tx
+            .blocking_send(command)
+            .map_err(|_| TimerError::ManagerStopped)
+    }
+
+    /// Queue a command without waiting
+    fn try_send(&self, command: TimerCommand<T>) -> Result<(), TimerError> {
+        self.command_tx.try_send(command).map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => TimerError::ChannelFull,
+            mpsc::error::TrySendError::Closed(_) => TimerError::ManagerStopped,
+        })
+    }
+
+    /// Send a command carrying a oneshot responder and await the reply
+    async fn request<R>(
+        &self,
+        command: impl FnOnce(oneshot::Sender<R>) -> TimerCommand<T>,
+    ) -> Result<R, TimerError> {
+        let (respond_to, response) = oneshot::channel();
+        self.send(command(respond_to)).await?;
+        response.await.map_err(|_| TimerError::ManagerStopped)
+    }
+
+    /// Whether the manager is still accepting commands
+    ///
+    /// A best-effort snapshot: the manager may stop right after this returns
+    /// `true`, and a shutdown already queued but not yet processed still reports
+    /// `true`. No round trip to the manager is made.
+    pub fn is_running(&self) -> bool {
+        !self.command_tx.is_closed()
+    }
+}
+
+impl<T: Clone + Send + 'static> TimerHandle<T> {
+    /// Cloneable handle that sends commands to the same manager
+    ///
+    /// Many tasks can set and cancel timers through their own copies while this
+    /// handle keeps sole ownership of the event receiver.
+    pub fn command_handle(&self) -> TimerCommander<T> {
+        self.commander.clone()
+    }
+
+    /// Take ownership of the event receiver, leaving this handle command-only
+    ///
+    /// Events already buffered stay in the receiver, so a new consumer can pick up
+    /// exactly where the old one stopped. Afterwards `recv_event` on this handle
+    /// returns `None` and `try_recv_event` reports `Disconnected` until a receiver
+    /// is installed again with `set_event_receiver`.
+    pub fn take_event_receiver(&mut self) -> Option<mpsc::Receiver<TimerEvent<T>>> {
+        self.event_rx.take()
+    }
+
+    /// Install an event receiver on this handle, returning the previous one
+    ///
+    /// Intended for giving back a receiver obtained from `take_event_receiver`.
+    pub fn set_event_receiver(
+        &mut self,
+        event_rx: mpsc::Receiver<TimerEvent<T>>,
+    ) -> Option<mpsc::Receiver<TimerEvent<T>>> {
+        self.event_rx.replace(event_rx)
+    }
+
+    /// Receive the next timer event (blocking)
+    pub async fn recv_event(&mut self) -> Option<TimerEvent<T>> {
+        self.event_rx.as_mut()?.recv().await
+    }
+
+    /// Try to receive a timer event (non-blocking)
+    pub fn try_recv_event(&mut self) -> Result<TimerEvent<T>, mpsc::error::TryRecvError> {
+        match self.event_rx.as_mut() {
+            Some(event_rx) => event_rx.try_recv(),
+            None => Err(mpsc::error::TryRecvError::Disconnected),
+        }
+    }
+
+    /// Receive a timer event from synchronous code, blocking the thread until one
+    /// arrives
+    ///
+    /// Returns `None` once the manager has stopped and every event was received.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from within an asynchronous execution context; use
+    /// [`recv_event`](Self::recv_event) there.
+    pub fn blocking_recv_event(&mut self) -> Option<TimerEvent<T>> {
+        self.event_rx.as_mut()?.blocking_recv()
+    }
+
+    /// Borrow the event receiver as a `Stream`, for use with `StreamExt` combinators
+    ///
+    /// The stream ends when the manager stops, or right away if the receiver was
+    /// taken with `take_event_receiver`.
+    pub fn event_stream(&mut self) -> impl Stream<Item = TimerEvent<T>> + '_ {
+        futures::stream::poll_fn(move |cx| match self.event_rx.as_mut() {
+            Some(event_rx) => event_rx.poll_recv(cx),
+            None => std::task::Poll::Ready(None),
+        })
+    }
+
+    /// Turn the handle into a `Stream` of its events
+    ///
+    /// This drops the handle's command side, so keep a
+    /// [`command_handle`](Self::command_handle) to go on sending commands:
+    /// otherwise the manager stops once the last sender is gone.
+    pub fn into_event_stream(mut self) -> impl Stream<Item = TimerEvent<T>> {
+        let mut event_rx = self.event_rx.take();
+        futures::stream::poll_fn(move |cx| match event_rx.as_mut() {
+            Some(event_rx) => event_rx.poll_recv(cx),
+            None => std::task::Poll::Ready(None),
+        })
+    }
+
+    /// Pass every already-buffered event to `f` (non-blocking)
+    ///
+    /// Returns as soon as the event channel is empty; it never waits for new events.
+    pub fn drain_events_with(&mut self, mut f: impl FnMut(TimerEvent<T>)) {
+        while let Ok(event) = self.try_recv_event() {
+            f(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::sleep;
+
+    #[tokio::test]
+    async fn test_timer_basic_functionality() {
+        let cancel_token = CancellationToken::new();
+        let (manager, mut handle) = TimerManager::new(
+            "test".to_string(),
+            Duration::from_millis(10),
+            10, // command buffer size
+            10, // event buffer size
+            cancel_token.clone(),
+        );
+
+        // Spawn the manager
+        tokio::spawn(manager.run());
+
+        // Set a short timer
+        handle
+            .set_timer("test_timer".to_string(), Duration::from_millis(50))
+            .await
+            .unwrap();
+
+        // Wait for expiration
+        let event = handle.recv_event().await.unwrap();
+        match event {
+            TimerEvent::TimerExpired { name, .. } => {
+                assert_eq!(name, "test_timer");
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_timer_cancel() {
+        let cancel_token = CancellationToken::new();
+        let (manager, mut handle) = TimerManager::new(
+            "test".to_string(),
+            Duration::from_millis(10),
+            10,
+            10,
+            cancel_token.clone(),
+        );
+
+        tokio::spawn(manager.run());
+
+        // Set a timer
+        handle
+            .set_timer("test_timer".to_string(), Duration::from_millis(100))
+            .await
+            .unwrap();
+
+        // Cancel it immediately
+        handle.cancel_timer("test_timer".to_string()).await.unwrap();
+
+        // Wait a bit to ensure it doesn't fire
+        sleep(Duration::from_millis(150)).await;
+
+        // Should not receive any events
+        assert!(handle.try_recv_event().is_err());
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_bounded_channel_backpressure() {
+        let cancel_token = CancellationToken::new();
+        let (manager, handle) = TimerManager::new(
+            "test".to_string(),
+            Duration::from_millis(10),
+            2, // small command buffer
+            2, // small event buffer
+            cancel_token.clone(),
+        );
+
+        tokio::spawn(manager.run());
+
+        // Fill up the command channel
+        handle
+            .set_timer("timer1".to_string(), Duration::from_millis(50))
+            .await
+            .unwrap();
+        handle
+            .set_timer("timer2".to_string(), Duration::from_millis(50))
+            .await
+            .unwrap();
+
+        // This should work with try_send
+        let result = handle.try_set_timer("timer3".to_string(), Duration::from_millis(50));
+        // Might succeed or fail depending on timing, but never reports a stopped manager
+        assert_ne!(result, Err(TimerError::ManagerStopped));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_token() {
+        let cancel_token = CancellationToken::new();
+        let (manager, mut handle) = TimerManager::new(
+            "test".to_string(),
+            Duration::from_millis(10),
+            10,
+            10,
+            cancel_token.clone(),
+        );
+
+        let manager_task = tokio::spawn(manager.run());
+
+        // Set a timer that should expire
+        handle
+            .set_timer("timer1".to_string(), Duration::from_millis(50))
+            .await
+            .unwrap();
+
+        // Cancel the token
+        cancel_token.cancel();
+
+        // Wait for the manager to shut down
+        let _ = manager_task.await;
+
+        // Try to set a timer after cancellation - this should fail
+        let result = handle.try_set_timer("timer2".to_string(), Duration::from_millis(50));
+        assert!(
+            result.is_err(),
+            "Setting timer after cancellation should fail"
+        );
+
+        // Should not receive any timer events since manager was cancelled
+        assert!(
+            handle.try_recv_event().is_err(),
+            "Should not receive events after cancellation"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_during_timer_operation() {
+        let cancel_token = CancellationToken::new();
+        let (manager, mut handle) = TimerManager::new(
+            "test".to_string(),
+            Duration::from_millis(10),
+            10,
+            10,
+            cancel_token.clone(),
+        );
+
+        let manager_task = tokio::spawn(manager.run());
+
+        // Set multiple timers
+        handle
+            .set_timer("timer1".to_string(), Duration::from_millis(100))
+            .await
+            .unwrap();
+        handle
+            .set_timer("timer2".to_string(), Duration::from_millis(200))
+            .await
+            .unwrap();
+
+        // Wait a bit to ensure timers are set
+        sleep(Duration::from_millis(20)).await;
+
+        // Cancel the token before timers expire
+        cancel_token.cancel();
+
+        // Wait for the manager to shut down
+        let _ = manager_task.await;
+
+        // Timers should not fire since manager was cancelled
+        assert!(
+            handle.try_recv_event().is_err(),
+            "No timer events should be received after cancellation"
+        );
+
+        // Subsequent operations should fail
+        let result = handle.try_set_timer("timer3".to_string(), Duration::from_millis(50));
+        assert!(result.is_err(), "Operations after cancellation should fail");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_clock_jump_notify() {
+        let (manager, mut handle) = TimerManager::builder("test")
+            .heartbeat(Duration::from_millis(10))
+            .clock_jump_detection(Duration::from_millis(100), ClockJumpPolicy::Notify)
+            .build();
+
+        tokio::spawn(manager.run());
+
+        // Let a few regular heartbeats pass
+        sleep(Duration::from_millis(30)).await;
+
+        // Jump the clock to simulate a suspended host
+        tokio::time::advance(Duration::from_millis(250)).await;
+
+        let event = tokio::time::timeout(Duration::from_secs(1), handle.recv_event())
+            .await
+            .unwrap()
+            .unwrap();
+        match event {
+            TimerEvent::ClockJump { gap } => assert!(gap >= Duration::from_millis(200)),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_clock_jump_fire_all() {
+        let (manager, mut handle) = TimerManager::builder("test")
+            .heartbeat(Duration::from_millis(10))
+            .clock_jump_detection(Duration::from_millis(100), ClockJumpPolicy::FireAll)
+            .build();
+
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("far_timer".to_string(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(30)).await;
+
+        tokio::time::advance(Duration::from_millis(250)).await;
+
+        let first = handle.recv_event().await.unwrap();
+        assert!(matches!(first, TimerEvent::ClockJump { .. }));
+        let second = tokio::time::timeout(Duration::from_secs(1), handle.recv_event())
+            .await
+            .unwrap()
+            .unwrap();
+        match second {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "far_timer"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_clock_jump_rephase() {
+        let (manager, mut handle) = TimerManager::builder("test")
+            .heartbeat(Duration::from_millis(10))
+            .clock_jump_detection(Duration::from_millis(100), ClockJumpPolicy::Rephase)
+            .build();
+
+        tokio::spawn(manager.run());
+
+        let start = tokio::time::Instant::now();
+        handle
+            .set_timer("timer".to_string(), Duration::from_millis(200))
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(30)).await;
+
+        // The deadline passes during the jump, which wakes the expiry sleep and
+        // the heartbeat together; the timer must be pushed back, not fired
+        tokio::time::advance(Duration::from_millis(400)).await;
+        let gap = match handle.recv_event().await.unwrap() {
+            TimerEvent::ClockJump { gap } => gap,
+            other => panic!("unexpected event: {:?}", other),
+        };
+        assert!(gap >= Duration::from_millis(400));
+        handle.ping().await.unwrap();
+        assert!(handle.try_recv_event().is_err());
+
+        // Everything but one heartbeat of the gap is added to the deadline
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "timer"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert_eq!(start.elapsed(), Duration::from_millis(190) + gap);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_simultaneous_timers_fire_in_set_order() {
+        let (mut manager, mut handle) = TimerManager::builder("test").build();
+
+        // Give every timer the exact same deadline
+        let expires_at = Instant::now();
+        for name in ["c", "a", "d", "b"] {
+            let seq = manager.next_seq;
+            manager.next_seq += 1;
+            manager.store(
+                name.to_string(),
+                TimerEntry {
+                    expires_at,
+                    seq,
+                    interval: None,
+                    iteration: 0,
+                    created_at: expires_at,
+                    reset_at: expires_at,
+                    priority: 0,
+                    escalate_below: None,
+                    payload: None,
+                    scheduled: Duration::ZERO,
+                },
+            );
+        }
+
+        manager.check_expired_timers().await;
+
+        let mut fired = Vec::new();
+        while let Ok(TimerEvent::TimerExpired { name, .. }) = handle.try_recv_event() {
+            fired.push(name);
+        }
+        assert_eq!(fired, vec!["c", "a", "d", "b"]);
+    }
+
+    #[tokio::test]
+    async fn test_take_metrics_resets_counters() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("fires".to_string(), Duration::from_millis(20))
+            .await
+            .unwrap();
+        handle
+            .set_timer("cancelled".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        handle.cancel_timer("cancelled".to_string()).await.unwrap();
+        handle.recv_event().await.unwrap();
+
+        let window = handle.take_metrics().await.unwrap();
+        assert_eq!(
+            window,
+            TimerMetrics {
+                timers_set: 2,
+                timers_cancelled: 1,
+                timers_fired: 1,
+                events_dropped: 0,
+                timers_evicted: 0,
+                events_dead_lettered: 0,
+                commands_processed: 3,
+            }
+        );
+
+        // The next window starts from zero, apart from the take itself
+        assert_eq!(
+            handle.take_metrics().await.unwrap(),
+            TimerMetrics {
+                commands_processed: 1,
+                ..TimerMetrics::default()
+            }
+        );
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_anonymous_timer() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        // A user timer occupying the first generated name must not be clobbered
+        handle
+            .set_timer("anon:0".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        let first = handle
+            .set_anonymous(Duration::from_millis(20))
+            .await
+            .unwrap();
+        let second = handle.set_anonymous(Duration::from_secs(10)).await.unwrap();
+        assert_eq!(first, "anon:1");
+        assert_eq!(second, "anon:2");
+
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, first),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_timer_confirmed() {
+        let (manager, handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer_confirmed("armed".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        // The timer was counted as set before the confirmation resolved
+        assert_eq!(handle.metrics().await.unwrap().timers_set, 1);
+
+        handle.shutdown().await.unwrap();
+        assert_eq!(
+            handle
+                .set_timer_confirmed("late".to_string(), Duration::from_secs(1))
+                .await,
+            Err(TimerError::ManagerStopped)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_policy_fire_all() {
+        let (manager, mut handle) = TimerManager::builder("test")
+            .shutdown_policy(ShutdownPolicy::FireAll)
+            .build();
+        let manager_task = tokio::spawn(manager.run());
+
+        handle
+            .set_timer("later".to_string(), Duration::from_secs(30))
+            .await
+            .unwrap();
+        handle
+            .set_timer("sooner".to_string(), Duration::from_secs(20))
+            .await
+            .unwrap();
+        handle.shutdown().await.unwrap();
+        manager_task.await.unwrap();
+
+        let mut fired = Vec::new();
+        while let Some(event) = handle.recv_event().await {
+            if let TimerEvent::TimerExpired { name, .. } = event {
+                fired.push(name);
+            }
+        }
+        assert_eq!(fired, vec!["sooner", "later"]);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_policy_wait_drain() {
+        let (manager, mut handle) = TimerManager::builder("test")
+            .shutdown_policy(ShutdownPolicy::WaitDrain {
+                timeout: Duration::from_millis(200),
+            })
+            .build();
+        let manager_task = tokio::spawn(manager.run());
+
+        handle
+            .set_timer("quick".to_string(), Duration::from_millis(30))
+            .await
+            .unwrap();
+        handle
+            .set_timer("slow".to_string(), Duration::from_secs(30))
+            .await
+            .unwrap();
+        handle.shutdown().await.unwrap();
+
+        // The manager keeps running until the drain timeout, firing what it can
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "quick"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        tokio::time::timeout(Duration::from_secs(1), manager_task)
+            .await
+            .expect("manager should stop after the drain timeout")
+            .unwrap();
+        assert!(handle.recv_event().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_ping_timeout() {
+        let (manager, handle) = TimerManager::builder("test").build();
+
+        // Not running yet: the ping is queued but never answered
+        assert!(!handle.ping_timeout(Duration::from_millis(50)).await);
+
+        let manager_task = tokio::spawn(manager.run());
+        assert!(handle.ping_timeout(Duration::from_secs(1)).await);
+
+        handle.shutdown().await.unwrap();
+        manager_task.await.unwrap();
+        assert!(!handle.ping_timeout(Duration::from_secs(1)).await);
+    }
+
+    #[test]
+    fn test_initial_capacity() {
+        let (manager, _handle) = TimerManager::builder("test").initial_capacity(5000).build();
+        assert!(manager.timers.capacity() >= 5000);
+    }
+
+    #[tokio::test]
+    async fn test_group_edge_events() {
+        let (manager, mut handle) = TimerManager::builder("test")
+            .watch_group("session:")
+            .build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("session:a".to_string(), Duration::from_millis(20))
+            .await
+            .unwrap();
+        handle
+            .set_timer("session:b".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        handle
+            .set_timer("other".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::GroupBecameNonEmpty { prefix } => assert_eq!(prefix, "session:"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "session:a"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.cancel_timer("session:b".to_string()).await.unwrap();
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::GroupBecameEmpty { prefix } => assert_eq!(prefix, "session:"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_hand_off_timers_between_managers() {
+        let (old_manager, old_handle) = TimerManager::builder("old").build();
+        let old_task = tokio::spawn(old_manager.run());
+
+        old_handle
+            .set_timer("handed_off".to_string(), Duration::from_millis(100))
+            .await
+            .unwrap();
+        old_handle
+            .set_timer("long".to_string(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        old_handle.shutdown().await.unwrap();
+
+        let remaining = old_task.await.unwrap();
+        let names: Vec<&str> = remaining.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["handed_off", "long"]);
+        assert!(remaining[0].1 <= Duration::from_millis(100));
+
+        let (new_manager, mut new_handle) = TimerManager::new_with_timers(
+            "new".to_string(),
+            Duration::from_millis(10),
+            10,
+            10,
+            CancellationToken::new(),
+            remaining,
+        );
+        tokio::spawn(new_manager.run());
+
+        match new_handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "handed_off"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        new_handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_watch_single_timer_lifecycle() {
+        use futures::StreamExt;
+
+        let (manager, handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        let mut lifecycle = Box::pin(handle.watch("watched".to_string()).await.unwrap());
+
+        handle
+            .set_timer("unrelated".to_string(), Duration::from_millis(10))
+            .await
+            .unwrap();
+        handle
+            .set_timer("watched".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        handle
+            .set_timer("watched".to_string(), Duration::from_millis(20))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            lifecycle.next().await,
+            Some(TimerLifecycleEvent::Set {
+                duration: Duration::from_secs(10)
+            })
+        );
+        assert_eq!(
+            lifecycle.next().await,
+            Some(TimerLifecycleEvent::Reset {
+                duration: Duration::from_millis(20)
+            })
+        );
+        assert_eq!(lifecycle.next().await, Some(TimerLifecycleEvent::Expired));
+
+        handle
+            .set_timer("watched".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        handle.cancel_timer("watched".to_string()).await.unwrap();
+        assert!(matches!(
+            lifecycle.next().await,
+            Some(TimerLifecycleEvent::Set { .. })
+        ));
+        assert_eq!(lifecycle.next().await, Some(TimerLifecycleEvent::Cancelled));
+
+        handle.shutdown().await.unwrap();
+        assert_eq!(lifecycle.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_command_rate_limit() {
+        let (manager, handle) = TimerManager::builder("test")
+            .command_rate_limit(Duration::from_millis(50))
+            .build();
+        tokio::spawn(manager.run());
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            handle.ping().await.unwrap();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancel_if_remaining() {
+        let (manager, handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("stale".to_string(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        handle
+            .set_timer("imminent".to_string(), Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        let long_lived = |remaining: Duration| remaining > Duration::from_secs(30);
+        assert!(handle
+            .cancel_if("stale".to_string(), long_lived)
+            .await
+            .unwrap());
+        assert!(!handle
+            .cancel_if("imminent".to_string(), long_lived)
+            .await
+            .unwrap());
+        assert!(!handle
+            .cancel_if("missing".to_string(), |_| true)
+            .await
+            .unwrap());
+        assert_eq!(handle.metrics().await.unwrap().timers_cancelled, 1);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_batch_complete_after_fire_and_cancel() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_batch_tracked(
+                "batch".to_string(),
+                vec![
+                    ("fires".to_string(), Duration::from_millis(20)),
+                    ("cancelled".to_string(), Duration::from_secs(10)),
+                ],
+            )
+            .await
+            .unwrap();
+
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "fires"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(handle.try_recv_event().is_err());
+
+        handle.cancel_timer("cancelled".to_string()).await.unwrap();
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::BatchComplete { batch_id } => assert_eq!(batch_id, "batch"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_duplicate_expiry_fires_once_per_sweep() {
+        let (mut manager, mut handle) = TimerManager::builder("test").build();
+        let interval = Duration::from_millis(10);
+
+        // A recurring timer is still stored after firing, so only the
+        // per-sweep guard keeps the second copy of its name from firing again
+        manager.insert_entry("tick".to_string(), interval, Some(interval), None);
+        tokio::time::advance(interval).await;
+        manager
+            .fire_expired(vec!["tick".to_string(), "tick".to_string()])
+            .await;
+
+        match handle.try_recv_event().unwrap() {
+            TimerEvent::TimerExpired {
+                name, iteration, ..
+            } => assert_eq!((name.as_str(), iteration), ("tick", 1)),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(handle.try_recv_event().is_err());
+        assert_eq!(manager.metrics.timers_fired, 1);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_shutdown_commands() {
+        let (manager, handle) = TimerManager::builder("test").build();
+
+        // Queue everything before the manager starts so processing order is fixed
+        handle
+            .set_timer("before".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        handle.shutdown().await.unwrap();
+        handle
+            .set_timer("between".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        handle.shutdown().await.unwrap();
+        handle
+            .set_timer("after".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        let remaining = manager.run().await;
+        let names: Vec<&str> = remaining.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["before"]);
+
+        // Trailing commands were dropped along with the channel
+        assert!(handle.try_shutdown().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_duration_stats() {
+        let (manager, handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        assert_eq!(handle.duration_stats().await.unwrap(), None);
+
+        handle
+            .set_timer("short".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        handle
+            .set_timer("long".to_string(), Duration::from_secs(30))
+            .await
+            .unwrap();
+
+        let stats = handle.duration_stats().await.unwrap().unwrap();
+        assert_eq!(stats.count, 2);
+        assert!(stats.min_remaining <= Duration::from_secs(10));
+        assert!(stats.min_remaining > Duration::from_secs(9));
+        assert!(stats.max_remaining <= Duration::from_secs(30));
+        assert!(stats.max_remaining > Duration::from_secs(29));
+        assert!(stats.mean_remaining <= Duration::from_secs(20));
+        assert!(stats.mean_remaining > Duration::from_secs(19));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_on_dropped_callback() {
+        let dropped = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorder = dropped.clone();
+        let (manager, handle) = TimerManager::builder("test")
+            .event_buffer(1)
+            .on_dropped(move |name| recorder.lock().unwrap().push(name.to_string()))
+            .build();
+        tokio::spawn(manager.run());
+
+        for (name, millis) in [("first", 10), ("second", 20), ("third", 30)] {
+            handle
+                .set_timer(name.to_string(), Duration::from_millis(millis))
+                .await
+                .unwrap();
+        }
+        sleep(Duration::from_millis(100)).await;
+
+        // The single-slot channel holds "first"; the rest are dropped in order
+        assert_eq!(*dropped.lock().unwrap(), vec!["second", "third"]);
+        assert_eq!(handle.metrics().await.unwrap().events_dropped, 2);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_heartbeat_tightens_for_near_deadline() {
+        let offset_secs = Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let virtual_offset = offset_secs.clone();
+        let (manager, mut handle) = TimerManager::builder("test")
+            .adaptive_heartbeat(Duration::from_millis(5), Duration::from_secs(5))
+            .now_fn(move || {
+                Instant::now()
+                    + Duration::from_secs(virtual_offset.load(std::sync::atomic::Ordering::SeqCst))
+            })
+            .build();
+        tokio::spawn(manager.run());
+
+        // With nothing pending the heartbeat relaxes to its maximum
+        sleep(Duration::from_millis(20)).await;
+
+        let start = Instant::now();
+        handle
+            .set_timer_confirmed("near".to_string(), Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        // The wake-up for the deadline was scheduled before the jump, so only the
+        // tightened heartbeat (half the remaining time) can notice it early
+        offset_secs.store(1, std::sync::atomic::Ordering::SeqCst);
+        let event = tokio::time::timeout(Duration::from_millis(800), handle.recv_event())
+            .await
+            .expect("adaptive heartbeat should sweep before the original deadline")
+            .unwrap();
+        assert!(matches!(event, TimerEvent::TimerExpired { .. }));
+        assert!(start.elapsed() >= Duration::from_millis(400));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_adaptive_heartbeat_with_clock_jump_detection() {
+        let (manager, mut handle) = TimerManager::builder("test")
+            .adaptive_heartbeat(Duration::from_millis(5), Duration::from_secs(5))
+            .clock_jump_detection(Duration::from_secs(1), ClockJumpPolicy::Notify)
+            .build();
+        tokio::spawn(manager.run());
+
+        // Idle ticks stay within the threshold instead of every `max`
+        sleep(Duration::from_secs(30)).await;
+        handle.ping().await.unwrap();
+        assert!(handle.try_recv_event().is_err());
+
+        // A real jump is still noticed
+        tokio::time::advance(Duration::from_secs(3)).await;
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::ClockJump { gap } => assert!(gap >= Duration::from_secs(3)),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_after_dependency() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("a".to_string(), Duration::from_millis(20))
+            .await
+            .unwrap();
+        handle
+            .set_after(
+                "b".to_string(),
+                Duration::from_millis(20),
+                "a".to_string(),
+                false,
+            )
+            .await
+            .unwrap();
+        handle
+            .set_timer("c".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        handle
+            .set_after(
+                "d".to_string(),
+                Duration::from_millis(1),
+                "c".to_string(),
+                false,
+            )
+            .await
+            .unwrap();
+        handle.cancel_timer("c".to_string()).await.unwrap();
+
+        let mut fired = Vec::new();
+        for _ in 0..2 {
+            if let Some(TimerEvent::TimerExpired { name, .. }) = handle.recv_event().await {
+                fired.push(name);
+            }
+        }
+        assert_eq!(fired, vec!["a", "b"]);
+
+        // "d" was never armed because "c" was cancelled
+        sleep(Duration::from_millis(50)).await;
+        assert!(handle.try_recv_event().is_err());
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_after_paused_prerequisite() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("a".to_string(), Duration::from_millis(20))
+            .await
+            .unwrap();
+        handle.pause_timer("a".to_string()).await.unwrap();
+        handle
+            .set_after(
+                "b".to_string(),
+                Duration::from_millis(10),
+                "a".to_string(),
+                false,
+            )
+            .await
+            .unwrap();
+        handle.resume_timer("a".to_string()).await.unwrap();
+
+        let mut fired = Vec::new();
+        for _ in 0..2 {
+            if let Some(TimerEvent::TimerExpired { name, .. }) = handle.recv_event().await {
+                fired.push(name);
+            }
+        }
+        assert_eq!(fired, vec!["a", "b"]);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expiry_reports_remaining_timers() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("first".to_string(), Duration::from_millis(20))
+            .await
+            .unwrap();
+        handle
+            .set_timer("last".to_string(), Duration::from_millis(60))
+            .await
+            .unwrap();
+
+        let mut remaining = Vec::new();
+        for _ in 0..2 {
+            if let Some(TimerEvent::TimerExpired {
+                remaining_timers, ..
+            }) = handle.recv_event().await
+            {
+                remaining.push(remaining_timers);
+            }
+        }
+        assert_eq!(remaining, vec![1, 0]);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_hand_off_event_receiver() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer_confirmed("buffered".to_string(), Duration::ZERO)
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(30)).await;
+
+        let mut event_rx = handle.take_event_receiver().unwrap();
+        assert!(handle.recv_event().await.is_none());
+        assert!(handle.take_event_receiver().is_none());
+
+        // The buffered event moved with the receiver
+        match event_rx.recv().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "buffered"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        assert!(handle.set_event_receiver(event_rx).is_none());
+        handle
+            .set_timer("after_return".to_string(), Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert!(matches!(
+            handle.recv_event().await,
+            Some(TimerEvent::TimerExpired { .. })
+        ));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_timer_reserved_waits_for_capacity() {
+        let (manager, mut handle) = TimerManager::builder("test").command_buffer(1).build();
+
+        // Fill the command channel before the manager starts draining it
+        handle
+            .set_timer("filler".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert!(handle
+            .try_set_timer("rejected".to_string(), Duration::ZERO)
+            .is_err());
+
+        tokio::spawn(manager.run());
+        handle
+            .set_timer_reserved("watchdog".to_string(), Duration::from_millis(10))
+            .await
+            .unwrap();
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "watchdog"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_recurring_timer_iterations() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_recurring_timer("tick".to_string(), Duration::from_millis(20))
+            .await
+            .unwrap();
+
+        let mut iterations = Vec::new();
+        for _ in 0..3 {
+            match handle.recv_event().await.unwrap() {
+                TimerEvent::TimerExpired {
+                    name, iteration, ..
+                } => {
+                    assert_eq!(name, "tick");
+                    iterations.push(iteration);
+                }
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+        assert_eq!(iterations, vec![1, 2, 3]);
+
+        // Re-setting restarts the count
+        handle
+            .set_recurring_timer("tick".to_string(), Duration::from_millis(20))
+            .await
+            .unwrap();
+        handle.ping().await.unwrap();
+        while handle.try_recv_event().is_ok() {}
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { iteration, .. } => assert_eq!(iteration, 1),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.cancel_timer("tick".to_string()).await.unwrap();
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_iteration_zero_for_one_shot_and_reset_on_cancel() {
+        let (mut manager, mut handle) = TimerManager::builder("test").build();
+        let interval = Duration::from_millis(10);
+
+        manager.insert_timer("once".to_string(), interval);
+        manager.insert_entry("tick".to_string(), interval, Some(interval), None);
+        let mut iterations = Vec::new();
+        for _ in 0..2 {
+            tokio::time::advance(interval).await;
+            manager.check_expired_timers().await;
+            while let Ok(TimerEvent::TimerExpired {
+                name, iteration, ..
+            }) = handle.try_recv_event()
+            {
+                iterations.push((name, iteration));
+            }
+        }
+        assert_eq!(
+            iterations,
+            vec![
+                ("once".to_string(), 0),
+                ("tick".to_string(), 1),
+                ("tick".to_string(), 2)
+            ]
+        );
+
+        // Cancelling and setting the timer again restarts the count
+        manager
+            .handle_command(TimerCommand::CancelTimer {
+                name: "tick".to_string(),
+            })
+            .await;
+        manager.insert_entry("tick".to_string(), interval, Some(interval), None);
+        tokio::time::advance(interval).await;
+        manager.check_expired_timers().await;
+        match handle.try_recv_event().unwrap() {
+            TimerEvent::TimerExpired {
+                name, iteration, ..
+            } => assert_eq!((name.as_str(), iteration), ("tick", 1)),
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drain_timers() {
+        let (manager, handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("b".to_string(), Duration::from_secs(20))
+            .await
+            .unwrap();
+        handle
+            .set_timer("a".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        let drained = handle.drain_timers().await.unwrap();
+        let names: Vec<&str> = drained.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+        assert!(drained[0].1 > Duration::from_secs(9));
+
+        assert!(handle.drain_timers().await.unwrap().is_empty());
+        assert_eq!(handle.metrics().await.unwrap().timers_cancelled, 2);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_custom_now_fn() {
+        let base = Instant::now();
+        let offset_secs = Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let virtual_offset = offset_secs.clone();
+        let (manager, mut handle) = TimerManager::builder("test")
+            .now_fn(move || {
+                base + Duration::from_secs(virtual_offset.load(std::sync::atomic::Ordering::SeqCst))
+            })
+            .build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer_confirmed("hour".to_string(), Duration::from_secs(3600))
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(30)).await;
+        assert!(handle.try_recv_event().is_err());
+
+        // Jump virtual time past the deadline; the next real heartbeat fires it
+        offset_secs.store(3600, std::sync::atomic::Ordering::SeqCst);
+        let event = tokio::time::timeout(Duration::from_secs(1), handle.recv_event())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(event, TimerEvent::TimerExpired { .. }));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reset_timers_skips_missing() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("a".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        handle
+            .set_timer("b".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        let updated = handle
+            .reset_timers(vec![
+                ("b".to_string(), Duration::from_millis(10)),
+                ("missing".to_string(), Duration::from_millis(10)),
+                ("a".to_string(), Duration::from_millis(20)),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(updated, vec!["b", "a"]);
+
+        let mut fired = Vec::new();
+        for _ in 0..2 {
+            if let Some(TimerEvent::TimerExpired { name, .. }) = handle.recv_event().await {
+                fired.push(name);
+            }
+        }
+        assert_eq!(fired, vec!["b", "a"]);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_capacity_evicts_furthest() {
+        let (manager, mut handle) = TimerManager::builder("test")
+            .max_timers(2)
+            .capacity_policy(CapacityPolicy::EvictFurthest)
+            .build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("near".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        handle
+            .set_timer("far".to_string(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        // Replacing an existing timer never evicts
+        handle
+            .set_timer("near".to_string(), Duration::from_secs(20))
+            .await
+            .unwrap();
+        handle
+            .set_timer("new".to_string(), Duration::from_secs(30))
+            .await
+            .unwrap();
+
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerEvicted { name, remaining } => {
+                assert_eq!(name, "far");
+                assert!(remaining > Duration::from_secs(59));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert_eq!(handle.metrics().await.unwrap().timers_evicted, 1);
+        assert_eq!(handle.duration_stats().await.unwrap().unwrap().count, 2);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_idle_shutdown() {
+        let (manager, mut handle) = TimerManager::builder("test")
+            .idle_shutdown(Duration::from_millis(100))
+            .build();
+        let manager_task = tokio::spawn(manager.run());
+
+        handle
+            .set_timer("timer".to_string(), Duration::from_millis(50))
+            .await
+            .unwrap();
+
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "timer"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        let expired_at = Instant::now();
+
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::ManagerStopped { reason } => assert_eq!(reason, StopReason::Idle),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(expired_at.elapsed() >= Duration::from_millis(90));
+        assert!(manager_task.await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_expiration_reports_armed_for() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("timer".to_string(), Duration::from_millis(100))
+            .await
+            .unwrap();
+        // Make sure the timer is armed before measuring the delay
+        handle.ping().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        handle
+            .set_timer("timer".to_string(), Duration::from_millis(50))
+            .await
+            .unwrap();
+
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired {
+                name,
+                armed_for,
+                since_last_reset,
+                ..
+            } => {
+                assert_eq!(name, "timer");
+                assert!(armed_for >= Duration::from_millis(110));
+                assert!(since_last_reset >= Duration::from_millis(50));
+                assert!(since_last_reset < armed_for);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "below the minimum")]
+    fn test_zero_heartbeat_rejected() {
+        let _ = TimerManager::builder("test").heartbeat(Duration::ZERO);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds maximum")]
+    fn test_adaptive_heartbeat_min_above_max_rejected() {
+        let _ = TimerManager::builder("test")
+            .adaptive_heartbeat(Duration::from_millis(50), Duration::from_millis(10));
+    }
+
+    #[tokio::test]
+    async fn test_dead_letter_receives_undeliverable_events() {
+        let (dead_letter_tx, mut dead_letter_rx) = mpsc::channel(1);
+        let (manager, handle) = TimerManager::builder("test")
+            .event_buffer(1)
+            .dead_letter(dead_letter_tx)
+            .build();
+        tokio::spawn(manager.run());
+
+        // Nobody reads events: the first fills the channel, the second is
+        // dead-lettered and the third is dropped
+        for (name, millis) in [("a", 10), ("b", 20), ("c", 30)] {
+            handle
+                .set_timer(name.to_string(), Duration::from_millis(millis))
+                .await
+                .unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        match dead_letter_rx.recv().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "b"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        let metrics = handle.metrics().await.unwrap();
+        assert_eq!(metrics.timers_fired, 1);
+        assert_eq!(metrics.events_dead_lettered, 1);
+        assert_eq!(metrics.events_dropped, 1);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_firing_within() {
+        let (manager, handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        for (name, secs) in [("late", 30), ("soon", 5), ("sooner", 1)] {
+            handle
+                .set_timer(name.to_string(), Duration::from_secs(secs))
+                .await
+                .unwrap();
+        }
+
+        let names: Vec<_> = handle
+            .firing_within(Duration::from_secs(10))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["sooner", "soon"]);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_timers_macro() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        crate::set_timers!(handle,
+            "b" => Duration::from_millis(40),
+            "a" => Duration::from_millis(20),
+        )
+        .await
+        .unwrap();
+
+        for expected in ["a", "b"] {
+            match handle.recv_event().await.unwrap() {
+                TimerEvent::TimerExpired { name, .. } => assert_eq!(name, expected),
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_swap_timer() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("state_a".to_string(), Duration::from_millis(30))
+            .await
+            .unwrap();
+        handle
+            .swap_timer(
+                "state_a".to_string(),
+                "state_b".to_string(),
+                Duration::from_millis(60),
+            )
+            .await
+            .unwrap();
+
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired {
+                name,
+                remaining_timers,
+                ..
+            } => {
+                assert_eq!(name, "state_b");
+                assert_eq!(remaining_timers, 0);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pause_heartbeat() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle.pause_heartbeat().await.unwrap();
+        handle
+            .set_timer("timer".to_string(), Duration::from_millis(20))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(handle.try_recv_event().is_err());
+
+        handle.resume_heartbeat().await.unwrap();
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "timer"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay_commands() {
+        let (record_tx, mut record_rx) = mpsc::unbounded_channel();
+        let (manager, handle) = TimerManager::builder("recorded")
+            .record_commands(record_tx)
+            .build();
+        let manager_task = tokio::spawn(manager.run());
+
+        handle
+            .set_timer("a".to_string(), Duration::from_millis(20))
+            .await
+            .unwrap();
+        handle
+            .set_timer("b".to_string(), Duration::from_millis(30))
+            .await
+            .unwrap();
+        handle.ping().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        handle.cancel_timer("b".to_string()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        handle.shutdown().await.unwrap();
+        manager_task.await.unwrap();
+
+        let mut log = Vec::new();
+        while let Ok(recorded) = record_rx.try_recv() {
+            log.push(recorded);
+        }
+        assert_eq!(log.len(), 4);
+        assert!(log[3].offset >= Duration::from_millis(60));
+
+        let (manager, mut handle) = TimerManager::builder("replayed").build();
+        let manager_task = tokio::spawn(manager.run());
+        TimerManager::replay(&handle, log).await.unwrap();
+
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "a"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(manager_task.await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_drain_events_with() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        for name in ["a", "b", "c"] {
+            handle
+                .set_timer(name.to_string(), Duration::from_millis(10))
+                .await
+                .unwrap();
+        }
+        sleep(Duration::from_millis(50)).await;
+
+        let mut fired = Vec::new();
+        handle.drain_events_with(|event| {
+            if let TimerEvent::TimerExpired { name, .. } = event {
+                fired.push(name);
+            }
+        });
+        assert_eq!(fired, ["a", "b", "c"]);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_handle_sleep() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        let start = Instant::now();
+        handle.sleep(Duration::from_millis(30)).await;
+        assert!(start.elapsed() >= Duration::from_millis(30));
+        assert!(handle.try_recv_event().is_err());
+
+        // Dropping an unfinished sleep cancels its timer
+        let abandoned = tokio::time::timeout(
+            Duration::from_millis(20),
+            handle.sleep(Duration::from_secs(10)),
+        )
+        .await;
+        assert!(abandoned.is_err());
+        handle.ping().await.unwrap();
+        assert_eq!(handle.duration_stats().await.unwrap(), None);
+
+        let metrics = handle.metrics().await.unwrap();
+        assert_eq!(metrics.timers_fired, 1);
+        assert_eq!(metrics.timers_cancelled, 1);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_closed_during_blocking_send_shuts_down() {
+        let (manager, mut handle) = TimerManager::builder("test")
+            .event_overflow_policy(EventOverflowPolicy::Block)
+            .closed_during_send(ClosedDuringSend::Shutdown)
+            .build();
+        let manager_task = tokio::spawn(manager.run());
+
+        handle
+            .set_timer("fires".to_string(), Duration::from_millis(10))
+            .await
+            .unwrap();
+        handle
+            .set_timer("pending".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        drop(handle.take_event_receiver());
+
+        let remaining = tokio::time::timeout(Duration::from_secs(1), manager_task)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].0, "pending");
+    }
+
+    #[tokio::test]
+    async fn test_with_broadcast() {
+        let (manager, commander, events) = TimerManager::with_broadcast("test", 16);
+        tokio::spawn(manager.run());
+
+        let mut first = events.subscribe();
+        let mut second = events.subscribe();
+        commander
+            .clone()
+            .set_timer("timer".to_string(), Duration::from_millis(20))
+            .await
+            .unwrap();
+
+        for subscriber in [&mut first, &mut second] {
+            match subscriber.recv().await.unwrap() {
+                TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "timer"),
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+
+        commander.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_escalated_timer_delivered_first() {
+        let (mut manager, mut handle) = TimerManager::builder("test").event_buffer(1).build();
+
+        manager.insert_timer("plain".to_string(), Duration::ZERO);
+        manager.insert_timer("urgent".to_string(), Duration::ZERO);
+        manager.timers.get_mut("urgent").unwrap().escalate_below = Some(Duration::from_secs(1));
+
+        // Only one event fits, and it goes to the escalated timer
+        manager.check_expired_timers().await;
+        match handle.try_recv_event().unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "urgent"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert_eq!(manager.metrics.events_dropped, 1);
+    }
+
+    #[tokio::test]
+    async fn test_fire_all_escalates_only_timers_within_threshold() {
+        let (manager, mut handle) = TimerManager::builder("test")
+            .shutdown_policy(ShutdownPolicy::FireAll)
+            .build();
+        let manager_task = tokio::spawn(manager.run());
+
+        // "far" is due first but is still outside its threshold at shutdown
+        handle
+            .set_timer_escalating(
+                "far".to_string(),
+                Duration::from_secs(10),
+                Duration::from_secs(1),
+            )
+            .await
+            .unwrap();
+        handle
+            .set_timer_escalating(
+                "near".to_string(),
+                Duration::from_secs(20),
+                Duration::from_secs(30),
+            )
+            .await
+            .unwrap();
+        handle.shutdown().await.unwrap();
+        manager_task.await.unwrap();
+
+        let mut fired = Vec::new();
+        while let Some(event) = handle.recv_event().await {
+            if let TimerEvent::TimerExpired { name, .. } = event {
+                fired.push(name);
+            }
+        }
+        assert_eq!(fired, vec!["near", "far"]);
+    }
+
+    #[test]
+    fn test_command_try_clone() {
+        let command: TimerCommand = TimerCommand::SetTimer {
+            name: "a".to_string(),
+            duration: Duration::from_secs(1),
+            payload: None,
+        };
+        match command.try_clone() {
+            Some(TimerCommand::SetTimer { name, duration, .. }) => {
+                assert_eq!(name, "a");
+                assert_eq!(duration, Duration::from_secs(1));
+            }
+            other => panic!("unexpected copy: {:?}", other),
+        }
+
+        let (respond_to, _response) = oneshot::channel();
+        let query: TimerCommand = TimerCommand::Count { respond_to };
+        assert!(query.try_clone().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_timer_replaced_event() {
+        let (manager, mut handle) = TimerManager::builder("test").lifecycle_events(true).build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("timer".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        handle
+            .set_timer("timer".to_string(), Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        for expected in [false, true] {
+            match handle.recv_event().await.unwrap() {
+                TimerEvent::TimerSet { name, replaced, .. } => {
+                    assert_eq!(name, "timer");
+                    assert_eq!(replaced, expected);
+                }
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerReplaced {
+                name,
+                old_remaining,
+                new_duration,
+            } => {
+                assert_eq!(name, "timer");
+                assert!(old_remaining > Duration::from_secs(9));
+                assert_eq!(new_duration, Duration::from_secs(5));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_graceful_cancel_restores_deadline() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        let start = Instant::now();
+        handle
+            .set_timer("flappy".to_string(), Duration::from_millis(80))
+            .await
+            .unwrap();
+        handle
+            .set_timer("expired_grace".to_string(), Duration::from_millis(50))
+            .await
+            .unwrap();
+        handle
+            .cancel_timer_graceful("flappy".to_string(), Duration::from_secs(1))
+            .await
+            .unwrap();
+        handle
+            .cancel_timer_graceful("expired_grace".to_string(), Duration::from_millis(10))
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(30)).await;
+
+        // Within grace the original deadline comes back; after grace the set applies
+        handle
+            .set_timer("flappy".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        handle
+            .set_timer("expired_grace".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "flappy"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(start.elapsed() < Duration::from_secs(1));
+        let stats = handle.duration_stats().await.unwrap().unwrap();
+        assert_eq!(stats.count, 1);
+        assert!(stats.min_remaining > Duration::from_secs(9));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_graceful_cancel_restores_dependents() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("a".to_string(), Duration::from_millis(40))
+            .await
+            .unwrap();
+        handle
+            .set_after(
+                "b".to_string(),
+                Duration::from_millis(10),
+                "a".to_string(),
+                false,
+            )
+            .await
+            .unwrap();
+        handle
+            .cancel_timer_graceful("a".to_string(), Duration::from_secs(1))
+            .await
+            .unwrap();
+        handle
+            .set_timer("a".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        // "b" still waits on the restored "a" instead of being discarded
+        for expected in ["a", "b"] {
+            match handle.recv_event().await.unwrap() {
+                TimerEvent::TimerExpired { name, .. } => assert_eq!(name, expected),
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_graceful_cancel_restores_sleeper() {
+        let (manager, handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        let commander = handle.command_handle();
+        let sleeper = tokio::spawn(async move {
+            commander
+                .sleep_named("nap".to_string(), Duration::from_millis(40))
+                .await
+        });
+        handle.ping().await.unwrap();
+        handle
+            .cancel_timer_graceful("nap".to_string(), Duration::from_secs(1))
+            .await
+            .unwrap();
+        handle
+            .set_timer("nap".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        let woken = tokio::time::timeout(Duration::from_secs(1), sleeper)
+            .await
+            .expect("restored timer should wake its sleeper at the original deadline")
+            .unwrap();
+        assert_eq!(woken, Ok(()));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_graceful_cancel_of_paused_timer() {
+        let (manager, mut handle) = TimerManager::builder("test").lifecycle_events(true).build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("paused".to_string(), Duration::from_secs(5))
+            .await
+            .unwrap();
+        handle.pause_timer("paused".to_string()).await.unwrap();
+        handle
+            .cancel_timer_graceful("paused".to_string(), Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(
+            handle.get_remaining("paused".to_string()).await.unwrap(),
+            None
+        );
+        handle
+            .set_timer("paused".to_string(), Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        // The timer comes back paused, and TimerSet reports the time it had left
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerSet { name, .. } => assert_eq!(name, "paused"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerSet {
+                name,
+                duration,
+                replaced,
+            } => {
+                assert_eq!(name, "paused");
+                assert!(duration <= Duration::from_secs(5));
+                assert!(!replaced);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert_eq!(
+            handle.get_remaining("paused".to_string()).await.unwrap(),
+            None
+        );
+        handle.resume_timer("paused".to_string()).await.unwrap();
+        let remaining = handle
+            .get_remaining("paused".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(remaining > Duration::from_secs(4) && remaining <= Duration::from_secs(5));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_query_config() {
+        let (manager, handle) = TimerManager::builder("configured")
+            .heartbeat(Duration::from_millis(5))
+            .command_buffer(8)
+            .event_buffer(16)
+            .max_timers(3)
+            .watch_group("job:")
+            .build();
+        tokio::spawn(manager.run());
+
+        let config = handle.config().await.unwrap();
+        assert_eq!(config.name, "configured");
+        assert_eq!(config.heartbeat_interval, Duration::from_millis(5));
+        assert_eq!(config.command_buffer_size, 8);
+        assert_eq!(config.event_buffer_size, Some(16));
+        assert_eq!(config.max_timers, Some(3));
+        assert_eq!(config.overflow_policy, EventOverflowPolicy::Drop);
+        assert_eq!(config.watched_groups, ["job:"]);
+        assert!(!config.dead_letter);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_inject_expiry() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("real".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        handle.inject_expiry("real".to_string()).await.unwrap();
+        handle.inject_expiry("synthetic".to_string()).await.unwrap();
+
+        for expected in ["real", "synthetic"] {
+            match handle.recv_event().await.unwrap() {
+                TimerEvent::TimerExpired { name, .. } => assert_eq!(name, expected),
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+        let remaining = handle.drain_timers().await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].0, "real");
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_creation_rate_limit() {
+        let (manager, mut handle) = TimerManager::builder("test")
+            .creation_rate_limit(2, Duration::from_secs(10))
+            .build();
+        tokio::spawn(manager.run());
+
+        for name in ["a", "b", "c"] {
+            handle
+                .set_timer(name.to_string(), Duration::from_secs(10))
+                .await
+                .unwrap();
+        }
+        // Replacing an existing timer is not limited
+        handle
+            .set_timer("a".to_string(), Duration::from_secs(20))
+            .await
+            .unwrap();
+
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerRejected { name, reason } => {
+                assert_eq!(name, "c");
+                assert_eq!(reason, RejectReason::CreationRateLimit);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        let stats = handle.duration_stats().await.unwrap().unwrap();
+        assert_eq!(stats.count, 2);
+        assert!(stats.max_remaining > Duration::from_secs(19));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fires_before_next_heartbeat() {
+        let (manager, mut handle) = TimerManager::builder("test")
+            .heartbeat(Duration::from_secs(1))
+            .build();
+        tokio::spawn(manager.run());
+
+        // Let the first heartbeat tick pass so the next one is a second away
+        sleep(Duration::from_millis(20)).await;
+        let start = Instant::now();
+        handle
+            .set_timer("timer".to_string(), Duration::from_millis(30))
+            .await
+            .unwrap();
+
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "timer"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(30));
+        assert!(elapsed < Duration::from_millis(500));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    /// Run with `cargo test --release -- --ignored bench_sweep` to see the timings
+    #[tokio::test]
+    #[ignore]
+    async fn bench_sweep_with_100k_timers() {
+        const TIMERS: usize = 100_000;
+        const SWEEPS: u32 = 100;
+
+        let (mut manager, _handle) = TimerManager::builder("bench")
+            .initial_capacity(TIMERS)
+            .build();
+        for i in 0..TIMERS {
+            manager.insert_timer(format!("timer:{i}"), Duration::from_secs(3600));
+        }
+
+        let start = Instant::now();
+        for _ in 0..SWEEPS {
+            manager.check_expired_timers().await;
+        }
+        let heap_sweep = start.elapsed() / SWEEPS;
+
+        // What every sweep used to cost: a scan over all timers
+        let start = Instant::now();
+        for _ in 0..SWEEPS {
+            let now = manager.now();
+            let due = manager
+                .timers
+                .values()
+                .filter(|entry| entry.expires_at <= now)
+                .count();
+            assert_eq!(due, 0);
+        }
+        let full_scan = start.elapsed() / SWEEPS;
+
+        println!("{TIMERS} timers: heap sweep {heap_sweep:?}, full scan {full_scan:?}");
+        assert!(heap_sweep < full_scan);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_with_100k_timers_touches_only_due_ones() {
+        const TIMERS: usize = 100_000;
+
+        let (mut manager, mut handle) = TimerManager::builder("test")
+            .initial_capacity(TIMERS)
+            .build();
+        for i in 0..TIMERS {
+            manager.insert_timer(format!("timer:{i}"), Duration::from_secs(3600));
+        }
+        manager.insert_timer("due".to_string(), Duration::ZERO);
+
+        // Only the due entry leaves the heap; the pending ones are never visited
+        manager.check_expired_timers().await;
+        assert_eq!(manager.expiry_heap.len(), TIMERS);
+        assert_eq!(manager.timers.len(), TIMERS);
+        assert!(matches!(
+            handle.try_recv_event(),
+            Ok(TimerEvent::TimerExpired { name, .. }) if name == "due"
+        ));
+        assert!(handle.try_recv_event().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_near_term_sweep_reconciles_expiry_heap() {
+        let (mut manager, mut handle) = TimerManager::builder("test").near_term_sweep(3).build();
+        // The first tick is a full sweep
+        manager.check_expired_timers().await;
+
+        let names: Vec<String> = (0..100).map(|i| format!("timer:{i}")).collect();
+        for name in &names {
+            manager.insert_timer(name.clone(), Duration::from_secs(3600));
+        }
+        manager.insert_timer("due".to_string(), Duration::ZERO);
+        manager.cancel_named(names);
+
+        // Near-term ticks fire what is due but leave the stale items in place
+        for _ in 0..2 {
+            manager.check_expired_timers().await;
+            assert_eq!(manager.expiry_heap.len(), 100);
+        }
+        assert!(matches!(
+            handle.try_recv_event(),
+            Ok(TimerEvent::TimerExpired { name, .. }) if name == "due"
+        ));
+
+        // The next full sweep drops them, and the cycle starts over
+        manager.check_expired_timers().await;
+        assert!(manager.expiry_heap.is_empty());
+        assert_eq!(manager.ticks_until_full_sweep, 2);
+        assert!(handle.try_recv_event().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_closed_event_channel_keeps_retrying_due_timers() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+        drop(handle.take_event_receiver());
+
+        crate::set_timers!(handle,
+            "a" => Duration::from_millis(50),
+            "b" => Duration::from_millis(50),
+        )
+        .await
+        .unwrap();
+
+        // Each sweep stops at the first undeliverable expiration; the sleeper due
+        // with it must still be woken by a later sweep
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            handle.sleep(Duration::from_millis(50)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(handle.snapshot().await.unwrap(), Vec::new());
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_full_diagnostics() {
+        let (manager, handle) = TimerManager::builder("diagnosed").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("b".to_string(), Duration::from_secs(20))
+            .await
+            .unwrap();
+        handle
+            .set_timer("a".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(20)).await;
+
+        let diagnostics = handle.diagnostics().await.unwrap();
+        assert_eq!(diagnostics.metrics.timers_set, 2);
+        let names: Vec<_> = diagnostics
+            .timers
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(diagnostics.config.name, "diagnosed");
+        assert!(diagnostics.uptime >= Duration::from_millis(20));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_recurring_timer_rearms_from_previous_deadline() {
+        let (mut manager, mut handle) = TimerManager::builder("test").build();
+        let interval = Duration::from_millis(20);
+        manager.insert_entry("tick".to_string(), interval, Some(interval), None);
+        let first_deadline = manager.timers["tick"].expires_at;
+
+        // Sweep well after the deadline; the next one must not inherit the lateness
+        manager
+            .fire_timers_due_by(first_deadline + Duration::from_millis(15))
+            .await;
+        assert!(matches!(
+            handle.try_recv_event(),
+            Ok(TimerEvent::TimerExpired { iteration: 1, .. })
+        ));
+        assert_eq!(manager.timers["tick"].expires_at, first_deadline + interval);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_recurring_timer_skips_missed_periods() {
+        let (mut manager, mut handle) = TimerManager::builder("test").build();
+        let interval = Duration::from_millis(10);
+        manager.insert_entry("tick".to_string(), interval, Some(interval), None);
+        let first_deadline = manager.timers["tick"].expires_at;
+
+        // A thousand periods pass without a sweep, as under a paused heartbeat
+        tokio::time::advance(Duration::from_secs(10)).await;
+        manager.fire_timers_due_by(manager.now()).await;
+        manager.fire_timers_due_by(manager.now()).await;
+
+        assert!(matches!(
+            handle.try_recv_event(),
+            Ok(TimerEvent::TimerExpired { iteration: 1, .. })
+        ));
+        assert!(handle.try_recv_event().is_err());
+        // Still on the original phase, at the first deadline ahead
+        assert_eq!(
+            manager.timers["tick"].expires_at,
+            first_deadline + interval * 1000
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_remaining() {
+        let (manager, handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("timer".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        let remaining = handle.get_remaining("timer".to_string()).await.unwrap();
+        assert!(remaining.unwrap() > Duration::from_secs(9));
+        assert_eq!(
+            handle.get_remaining("missing".to_string()).await.unwrap(),
+            None
+        );
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_list_timers() {
+        let (manager, handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        for (name, secs) in [("late", 30), ("early", 10), ("middle", 20)] {
+            handle
+                .set_timer(name.to_string(), Duration::from_secs(secs))
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            handle.list_timers().await.unwrap(),
+            ["early", "middle", "late"]
+        );
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_events() {
+        let (manager, mut handle) = TimerManager::builder("test").lifecycle_events(true).build();
+        tokio::spawn(manager.run());
+
+        for name in ["a", "b", "c"] {
+            handle
+                .set_timer(name.to_string(), Duration::from_secs(10))
+                .await
+                .unwrap();
+        }
+        handle.cancel_timer("a".to_string()).await.unwrap();
+        handle.cancel_timer("missing".to_string()).await.unwrap();
+        handle.cancel_all_timers().await.unwrap();
+        handle.cancel_all_timers().await.unwrap();
+        handle.ping().await.unwrap();
+
+        for expected in ["a", "b", "c"] {
+            match handle.try_recv_event().unwrap() {
+                TimerEvent::TimerSet { name, duration, .. } => {
+                    assert_eq!(name, expected);
+                    assert_eq!(duration, Duration::from_secs(10));
+                }
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+        match handle.try_recv_event().unwrap() {
+            TimerEvent::TimerCancelled { name } => assert_eq!(name, "a"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        match handle.try_recv_event().unwrap() {
+            TimerEvent::AllTimersCancelled { count } => assert_eq!(count, 2),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(handle.try_recv_event().is_err());
+
+        // Paused timers are cancelled and counted too
+        for name in ["d", "e"] {
+            handle
+                .set_timer(name.to_string(), Duration::from_secs(10))
+                .await
+                .unwrap();
+            handle.pause_timer(name.to_string()).await.unwrap();
+        }
+        handle.cancel_all_timers().await.unwrap();
+        handle.ping().await.unwrap();
+        for _ in ["d", "e"] {
+            assert!(matches!(
+                handle.try_recv_event(),
+                Ok(TimerEvent::TimerSet { .. })
+            ));
+        }
+        match handle.try_recv_event().unwrap() {
+            TimerEvent::AllTimersCancelled { count } => assert_eq!(count, 2),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(handle.try_recv_event().is_err());
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_timer_payload() {
+        #[derive(Debug, Clone, PartialEq)]
+        enum State {
+            Idle,
+            Retry(u32),
+        }
+
+        let (manager, mut handle) = TimerManagerBuilder::<State>::new("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer_with_payload(
+                "retry".to_string(),
+                Duration::from_millis(20),
+                State::Retry(2),
+            )
+            .await
+            .unwrap();
+        handle
+            .set_timer("plain".to_string(), Duration::from_millis(10))
+            .await
+            .unwrap();
+        handle
+            .set_timer_with_payload(
+                "cancelled".to_string(),
+                Duration::from_millis(5),
+                State::Idle,
+            )
+            .await
+            .unwrap();
+        handle.cancel_timer("cancelled".to_string()).await.unwrap();
+
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, payload, .. } => {
+                assert_eq!(name, "plain");
+                assert_eq!(payload, None);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, payload, .. } => {
+                assert_eq!(name, "retry");
+                assert_eq!(payload, Some(State::Retry(2)));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancel_drops_payload() {
+        let payload = Arc::new(());
+        let (manager, handle) = TimerManagerBuilder::<Arc<()>>::new("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer_with_payload("a".to_string(), Duration::from_secs(10), payload.clone())
+            .await
+            .unwrap();
+        handle.ping().await.unwrap();
+        assert_eq!(Arc::strong_count(&payload), 2);
+
+        handle.cancel_timer("a".to_string()).await.unwrap();
+        handle.ping().await.unwrap();
+        assert_eq!(Arc::strong_count(&payload), 1);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_command_handle_shared_across_tasks() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        let mut producers = Vec::new();
+        for i in 0..3 {
+            let commands = handle.command_handle();
+            producers.push(tokio::spawn(async move {
+                commands
+                    .set_timer(format!("timer:{i}"), Duration::from_millis(10))
+                    .await
+                    .unwrap();
+            }));
+        }
+        for producer in producers {
+            producer.await.unwrap();
+        }
+
+        let mut fired = Vec::new();
+        for _ in 0..3 {
+            match handle.recv_event().await.unwrap() {
+                TimerEvent::TimerExpired { name, .. } => fired.push(name),
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+        fired.sort();
+        assert_eq!(fired, ["timer:0", "timer:1", "timer:2"]);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reset_and_extend_timer() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("a".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        handle
+            .reset_timer("a".to_string(), Duration::from_secs(20))
+            .await
+            .unwrap();
+        assert_eq!(
+            handle
+                .reset_timer("missing".to_string(), Duration::from_secs(20))
+                .await,
+            Err(TimerError::TimerNotFound)
+        );
+
+        handle
+            .extend_timer("a".to_string(), Duration::from_secs(5))
+            .await
+            .unwrap();
+        let remaining = handle
+            .get_remaining("a".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(remaining > Duration::from_secs(24) && remaining <= Duration::from_secs(25));
+
+        handle
+            .extend_timer("missing".to_string(), Duration::from_secs(5))
+            .await
+            .unwrap();
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerNotFound { name } => assert_eq!(name, "missing"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert_eq!(handle.list_timers().await.unwrap(), ["a"]);
+
+        // Overflowing the deadline is refused instead of stopping the manager
+        handle
+            .extend_timer("a".to_string(), Duration::MAX)
+            .await
+            .unwrap();
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerRejected { name, reason } => {
+                assert_eq!(name, "a");
+                assert_eq!(reason, RejectReason::DeadlineOverflow);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        let remaining = handle
+            .get_remaining("a".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(remaining <= Duration::from_secs(25));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_builder_defaults_and_name() {
+        let (manager, handle) = TimerManager::builder("first")
+            .name("renamed")
+            .command_buffer(8)
+            .build();
+        let config = manager.effective_config();
+        assert_eq!(config.name, "renamed");
+        assert_eq!(config.heartbeat_interval, Duration::from_millis(10));
+        assert_eq!(config.command_buffer_size, 8);
+        assert_eq!(config.event_buffer_size, Some(100));
+        drop(handle);
+    }
+
+    #[tokio::test]
+    async fn test_errors_after_manager_stopped() {
+        let (manager, handle) = TimerManager::builder("test").command_buffer(1).build();
+
+        // Nobody drains the channel yet, so the second command does not fit
+        handle
+            .try_set_timer("a".to_string(), Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(
+            handle.try_set_timer("b".to_string(), Duration::from_secs(1)),
+            Err(TimerError::ChannelFull)
+        );
+
+        drop(manager);
+        assert_eq!(
+            handle
+                .set_timer("c".to_string(), Duration::from_secs(1))
+                .await,
+            Err(TimerError::ManagerStopped)
+        );
+        assert_eq!(handle.try_shutdown(), Err(TimerError::ManagerStopped));
+    }
+
+    #[tokio::test]
+    async fn test_commands_queued_before_cancellation_are_processed() {
+        let cancel_token = CancellationToken::new();
+        let (manager, handle) = TimerManager::builder("test")
+            .cancel_token(cancel_token.clone())
+            .build();
+
+        // Queue commands before the manager observes the cancellation
+        handle
+            .set_timer("a".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        let commander = handle.command_handle();
+        let ping = tokio::spawn(async move { commander.ping().await });
+        tokio::task::yield_now().await;
+        handle
+            .set_timer("b".to_string(), Duration::from_secs(20))
+            .await
+            .unwrap();
+        cancel_token.cancel();
+
+        let remaining = manager.run().await;
+        let names: Vec<_> = remaining.into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(ping.await.unwrap(), Ok(()));
+        assert_eq!(
+            handle
+                .set_timer("c".to_string(), Duration::from_secs(1))
+                .await,
+            Err(TimerError::ManagerStopped)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_resume_timer() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("a".to_string(), Duration::from_millis(50))
+            .await
+            .unwrap();
+        handle.pause_timer("a".to_string()).await.unwrap();
+        // Pausing twice or pausing a missing timer is a no-op
+        handle.pause_timer("a".to_string()).await.unwrap();
+        handle.pause_timer("missing".to_string()).await.unwrap();
+        assert!(handle.list_timers().await.unwrap().is_empty());
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(handle.try_recv_event().is_err());
+
+        handle.resume_timer("a".to_string()).await.unwrap();
+        let remaining = handle
+            .get_remaining("a".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(remaining > Duration::from_millis(40));
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "a"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pause_all_preserves_remaining() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("a".to_string(), Duration::from_millis(50))
+            .await
+            .unwrap();
+        handle.pause_all().await.unwrap();
+        handle
+            .set_timer("b".to_string(), Duration::from_millis(60))
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(handle.try_recv_event().is_err());
+
+        handle.resume_all().await.unwrap();
+        let a = handle
+            .get_remaining("a".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        let b = handle
+            .get_remaining("b".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(a > Duration::from_millis(40) && a <= Duration::from_millis(50));
+        assert!(b > Duration::from_millis(50) && b <= Duration::from_millis(60));
+
+        let mut fired = Vec::new();
+        for _ in 0..2 {
+            match handle.recv_event().await.unwrap() {
+                TimerEvent::TimerExpired { name, .. } => fired.push(name),
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+        assert_eq!(fired, ["a", "b"]);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_max_timers_rejects_new_names() {
+        let (manager, mut handle) = TimerManager::builder("test").max_timers(2).build();
+        tokio::spawn(manager.run());
+
+        for name in ["a", "b", "c"] {
+            handle
+                .set_timer(name.to_string(), Duration::from_secs(10))
+                .await
+                .unwrap();
+        }
+        // Replacing an existing name is always allowed
+        handle
+            .set_timer("a".to_string(), Duration::from_secs(20))
+            .await
+            .unwrap();
+
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerRejected { name, reason } => {
+                assert_eq!(name, "c");
+                assert_eq!(reason, RejectReason::AtCapacity);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert_eq!(handle.list_timers().await.unwrap(), ["b", "a"]);
+        assert!(handle.try_recv_event().is_err());
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_zero_duration_fires_without_waiting_for_heartbeat() {
+        let heartbeat = Duration::from_millis(200);
+        let (manager, mut handle) = TimerManager::builder("test").heartbeat(heartbeat).build();
+        tokio::spawn(manager.run());
+        // Let the first (immediate) heartbeat tick pass
+        handle.ping().await.unwrap();
+
+        handle
+            .set_timer("now".to_string(), Duration::ZERO)
+            .await
+            .unwrap();
+        let event = tokio::time::timeout(2 * heartbeat, handle.recv_event())
+            .await
+            .expect("zero-duration timer did not fire within two heartbeats");
+        match event.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "now"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stats_count_dropped_expirations() {
+        let (manager, handle) = TimerManager::builder("test").event_buffer(1).build();
+        tokio::spawn(manager.run());
+
+        // Nobody reads events, so only the first expiration fits
+        for name in ["a", "b", "c"] {
+            handle
+                .set_timer(name.to_string(), Duration::ZERO)
+                .await
+                .unwrap();
+        }
+        handle
+            .set_timer("pending".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        handle.take_metrics().await.unwrap();
+
+        let stats = handle.stats().await.unwrap();
+        assert_eq!(
+            stats,
+            TimerStats {
+                active_timers: 1,
+                total_fired: 1,
+                dropped_events: 2,
+            }
+        );
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest_overflow_policy() {
+        let dropped = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = dropped.clone();
+        let (manager, mut handle) = TimerManager::builder("test")
+            .event_buffer(1)
+            .event_overflow_policy(EventOverflowPolicy::DropOldest)
+            .on_dropped(move |name| recorded.lock().unwrap().push(name.to_string()))
+            .build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timers(vec![
+                ("a".to_string(), Duration::ZERO),
+                ("b".to_string(), Duration::ZERO),
+                ("c".to_string(), Duration::ZERO),
+            ])
+            .await
+            .unwrap();
+        handle.ping().await.unwrap();
+
+        let mut fired = Vec::new();
+        for _ in 0..2 {
+            match handle.recv_event().await.unwrap() {
+                TimerEvent::TimerExpired { name, .. } => fired.push(name),
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+        // "a" fit in the channel, "b" was the oldest held-back event when "c" arrived
+        assert_eq!(fired, ["a", "c"]);
+        assert_eq!(*dropped.lock().unwrap(), ["b"]);
+        assert_eq!(handle.stats().await.unwrap().dropped_events, 1);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_timers_is_one_command() {
+        let (manager, handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        let timers = (0..50)
+            .map(|i| (format!("timer:{i}"), Duration::from_secs(10)))
+            .collect();
+        handle.set_timers(timers).await.unwrap();
+
+        let metrics = handle.metrics().await.unwrap();
+        assert_eq!(metrics.timers_set, 50);
+        // Only the batch: the metrics query is counted after it replies
+        assert_eq!(metrics.commands_processed, 1);
+        assert_eq!(handle.list_timers().await.unwrap().len(), 50);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sleep_named_leaves_other_events_queued() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("other".to_string(), Duration::from_millis(10))
+            .await
+            .unwrap();
+        handle
+            .sleep_named("mine".to_string(), Duration::from_millis(40))
+            .await
+            .unwrap();
+
+        // The other expiration is still waiting, and "mine" produced no event
+        match handle.try_recv_event().unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "other"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(handle.try_recv_event().is_err());
+
+        let commander = handle.command_handle();
+        let sleeping = tokio::spawn(async move {
+            commander
+                .sleep_named("cancelled".to_string(), Duration::from_secs(10))
+                .await
+        });
+        while handle.list_timers().await.unwrap().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        handle.cancel_timer("cancelled".to_string()).await.unwrap();
+        assert_eq!(sleeping.await.unwrap(), Err(TimerError::TimerNotFound));
+
+        let commander = handle.command_handle();
+        let sleeping = tokio::spawn(async move {
+            commander
+                .sleep_named("stopped".to_string(), Duration::from_secs(10))
+                .await
+        });
+        while handle.list_timers().await.unwrap().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        handle.shutdown().await.unwrap();
+        assert_eq!(sleeping.await.unwrap(), Err(TimerError::ManagerStopped));
+    }
+
+    #[tokio::test]
+    async fn test_set_timer_at() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        let deadline = Instant::now() + Duration::from_millis(40);
+        handle
+            .set_timer_at("future".to_string(), deadline)
+            .await
+            .unwrap();
+        handle
+            .set_timer_at("past".to_string(), Instant::now() - Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        for expected in ["past", "future"] {
+            match handle.recv_event().await.unwrap() {
+                TimerEvent::TimerExpired { name, .. } => assert_eq!(name, expected),
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+        assert!(Instant::now() >= deadline);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_policy_drain_expired() {
+        let (manager, mut handle) = TimerManager::builder("test")
+            .shutdown_policy(ShutdownPolicy::DrainExpired)
+            .build();
+        let manager_task = tokio::spawn(manager.run());
+
+        // Keep the expired timer from being swept before the shutdown
+        handle.pause_heartbeat().await.unwrap();
+        handle
+            .set_timer("expired".to_string(), Duration::from_millis(10))
+            .await
+            .unwrap();
+        handle
+            .set_timer("pending".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(handle.try_recv_event().is_err());
+
+        handle.shutdown().await.unwrap();
+        let remaining = manager_task.await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].0, "pending");
+
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "expired"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(handle.recv_event().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_and_report() {
+        let (manager, handle) = TimerManager::builder("test").build();
+        let manager_task = tokio::spawn(manager.run());
+
+        handle
+            .set_timer("late".to_string(), Duration::from_secs(20))
+            .await
+            .unwrap();
+        handle
+            .set_timer("early".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        let abandoned = handle.shutdown_and_report().await.unwrap();
+        assert_eq!(abandoned, ["early", "late"]);
+        manager_task.await.unwrap();
+        assert_eq!(
+            handle.shutdown_and_report().await,
+            Err(TimerError::ManagerStopped)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_contains_timer() {
+        let (manager, handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("a".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert!(handle.contains_timer("a".to_string()).await.unwrap());
+        assert!(!handle.contains_timer("b".to_string()).await.unwrap());
+
+        handle.cancel_timer("a".to_string()).await.unwrap();
+        assert!(!handle.contains_timer("a".to_string()).await.unwrap());
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_count_timers() {
+        let (manager, handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        assert_eq!(handle.count_timers().await.unwrap(), 0);
+        for name in ["a", "b", "a"] {
+            handle
+                .set_timer(name.to_string(), Duration::from_secs(10))
+                .await
+                .unwrap();
+        }
+        assert_eq!(handle.count_timers().await.unwrap(), 2);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_heartbeat() {
+        let (manager, mut handle) = TimerManager::builder("test")
+            .heartbeat(Duration::from_millis(10))
+            .build();
+        tokio::spawn(manager.run());
+
+        handle.set_heartbeat(Duration::ZERO).await.unwrap();
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::HeartbeatRejected { interval } => assert_eq!(interval, Duration::ZERO),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        let config = handle.config().await.unwrap();
+        assert_eq!(config.heartbeat_interval, Duration::from_millis(10));
+
+        handle
+            .set_heartbeat(Duration::from_millis(50))
+            .await
+            .unwrap();
+        let config = handle.config().await.unwrap();
+        assert_eq!(config.heartbeat_interval, Duration::from_millis(50));
+
+        handle
+            .set_timer("timer".to_string(), Duration::from_millis(20))
+            .await
+            .unwrap();
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "timer"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_same_sweep_fires_in_deadline_order() {
+        let (mut manager, mut handle) = TimerManager::builder("test").build();
+
+        // Set out of deadline order; all are overdue by the time of the sweep
+        let now = Instant::now();
+        for (name, ago) in [("b", 20), ("c", 10), ("a", 30)] {
+            let expires_at = now - Duration::from_millis(ago);
+            let seq = manager.next_seq;
+            manager.next_seq += 1;
+            manager.store(
+                name.to_string(),
+                TimerEntry {
+                    expires_at,
+                    seq,
+                    interval: None,
+                    iteration: 0,
+                    created_at: expires_at,
+                    reset_at: expires_at,
+                    priority: 0,
+                    escalate_below: None,
+                    payload: None,
+                    scheduled: Duration::ZERO,
+                },
+            );
+        }
+
+        manager.check_expired_timers().await;
+
+        let mut fired = Vec::new();
+        while let Ok(TimerEvent::TimerExpired { name, .. }) = handle.try_recv_event() {
+            fired.push(name);
+        }
+        assert_eq!(fired, vec!["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_sleep_follows_paused_clock() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle.pause_all().await.unwrap();
+        let frozen = tokio::time::timeout(
+            Duration::from_millis(60),
+            handle.sleep(Duration::from_millis(20)),
+        )
+        .await;
+        assert!(frozen.is_err());
+
+        handle.resume_all().await.unwrap();
+        handle.sleep(Duration::from_millis(20)).await;
+        assert_eq!(handle.count_timers().await.unwrap(), 0);
+        assert!(handle.try_recv_event().is_err());
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_timer_if_absent() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .try_set_timer_if_absent("timer".to_string(), Duration::from_secs(10))
+            .unwrap();
+        handle
+            .try_set_timer_if_absent("timer".to_string(), Duration::from_millis(10))
+            .unwrap();
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerRejected { name, reason } => {
+                assert_eq!(name, "timer");
+                assert_eq!(reason, RejectReason::AlreadyExists);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        let remaining = handle
+            .get_remaining("timer".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(remaining > Duration::from_secs(9));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expiration_reports_scheduled_duration() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        handle
+            .set_timer("once".to_string(), Duration::from_millis(20))
+            .await
+            .unwrap();
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired {
+                name, scheduled, ..
+            } => {
+                assert_eq!(name, "once");
+                assert_eq!(scheduled, Duration::from_millis(20));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle
+            .set_recurring_timer("tick".to_string(), Duration::from_millis(15))
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            match handle.recv_event().await.unwrap() {
+                TimerEvent::TimerExpired { scheduled, .. } => {
+                    assert_eq!(scheduled, Duration::from_millis(15));
+                }
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_follows_paused_tokio_clock() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        let real_start = std::time::Instant::now();
+        handle
+            .set_timer("timer".to_string(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        handle.ping().await.unwrap();
+
+        tokio::time::advance(Duration::from_secs(59)).await;
+        handle.ping().await.unwrap();
+        assert!(handle.try_recv_event().is_err());
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired {
+                name, armed_for, ..
+            } => {
+                assert_eq!(name, "timer");
+                assert!(armed_for >= Duration::from_secs(60));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(real_start.elapsed() < Duration::from_secs(5));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_is_running() {
+        let (manager, handle) = TimerManager::builder("test").build();
+        let manager_task = tokio::spawn(manager.run());
+
+        assert!(handle.is_running());
+        handle.shutdown().await.unwrap();
+        manager_task.await.unwrap();
+        assert!(!handle.is_running());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_timers() {
+        let (manager, handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        for name in ["a", "b", "c"] {
+            handle
+                .set_timer(name.to_string(), Duration::from_secs(10))
+                .await
+                .unwrap();
+        }
+        let names = vec!["a".to_string(), "c".to_string(), "missing".to_string()];
+        assert_eq!(handle.cancel_timers(names).await.unwrap(), 2);
+        assert_eq!(handle.list_timers().await.unwrap(), vec!["b".to_string()]);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancel_by_prefix() {
+        let (manager, handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
+
+        for name in [
+            "session:1:heartbeat",
+            "session:1:timeout",
+            "session:12:timeout",
+        ] {
+            handle
+                .set_timer(name.to_string(), Duration::from_secs(10))
+                .await
+                .unwrap();
+        }
+        handle
+            .pause_timer("session:1:timeout".to_string())
+            .await
+            .unwrap();
+
+        let cancelled = handle
+            .cancel_by_prefix("session:1:".to_string())
+            .await
+            .unwrap();
+        assert_eq!(cancelled, 2);
+        assert_eq!(
+            handle.list_timers().await.unwrap(),
+            vec!["session:12:timeout".to_string()]
+        );
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[test]
+    fn test_spawn_on_thread() {
+        let (manager, mut handle) = TimerManager::builder("threaded").build();
+        let thread = manager.spawn_on_thread().unwrap();
+
+        handle
+            .blocking_set_timer("short".to_string(), Duration::from_millis(10))
+            .unwrap();
+        handle
+            .blocking_set_timer("long".to_string(), Duration::from_secs(60))
+            .unwrap();
+        let name = loop {
+            match handle.try_recv_event() {
+                Ok(TimerEvent::TimerExpired { name, .. }) => break name,
+                Ok(other) => panic!("unexpected event: {:?}", other),
+                Err(_) => std::thread::sleep(Duration::from_millis(5)),
+            }
+        };
+        assert_eq!(name, "short");
+
+        handle.try_shutdown().unwrap();
+        let remaining = thread.join().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].0, "long");
+    }
+
+    #[test]
+    fn test_blocking_methods() {
+        let (manager, mut handle) = TimerManager::builder("threaded").build();
+        let thread = manager.spawn_on_thread().unwrap();
+
+        handle
+            .blocking_set_timer("cancelled".to_string(), Duration::from_millis(20))
+            .unwrap();
+        handle
+            .blocking_set_timer("fired".to_string(), Duration::from_millis(30))
+            .unwrap();
+        handle
+            .blocking_cancel_timer("cancelled".to_string())
+            .unwrap();
+        match handle.blocking_recv_event().unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "fired"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle
+            .blocking_set_timer("pending".to_string(), Duration::from_secs(60))
+            .unwrap();
+        handle.blocking_cancel_all_timers().unwrap();
+        handle.blocking_shutdown().unwrap();
+        assert!(thread.join().unwrap().is_empty());
+        assert!(handle.blocking_recv_event().is_none());
+        assert_eq!(handle.blocking_shutdown(), Err(TimerError::ManagerStopped));
+    }
+
+    #[tokio::test]
+    #[should_panic]
+    async fn test_blocking_method_panics_in_runtime() {
+        let (_manager, handle) = TimerManager::builder("test").build();
+        let _ = handle.blocking_set_timer("timer".to_string(), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_priority_survives_full_event_channel() {
+        let (manager, mut handle) = TimerManager::builder("test").event_buffer(1).build();
+        tokio::spawn(manager.run());
+
+        handle.pause_heartbeat().await.unwrap();
+        handle
+            .set_timer_with_priority("low".to_string(), Duration::from_millis(10), 0)
+            .await
+            .unwrap();
+        handle
+            .set_timer_with_priority("high".to_string(), Duration::from_millis(20), 10)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        handle.resume_heartbeat().await.unwrap();
+        handle.ping().await.unwrap();
 
-                // if was_replaced {
-                //     log::debug!("Timer '{}' updated in manager '{}'", name, self.name);
-                // } else {
-                //     log::debug!("Timer '{}' set in manager '{}' to expire in {:?}", name, self.name, duration);
-                // }
-            }
-            TimerCommand::CancelTimer { name } => {
-                if self.timers.remove(&name).is_some() {
-                    //log::debug!("Timer '{}' canceled in manager '{}'", name, self.name);
-                }
-            }
-            TimerCommand::CancelAllTimers => {
-                //let count = self.timers.len();
-                self.timers.clear();
-                //log::debug!("Canceled all {} timer(s) in manager '{}'", count, self.name);
-            }
-            TimerCommand::Shutdown => {
-                //log::info!("Timer manager '{}' shutting down", self.name);
-                shutdown = true;
-            }
+        match handle.try_recv_event().unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "high"),
+            other => panic!("unexpected event: {:?}", other),
         }
-        shutdown
+        assert!(handle.try_recv_event().is_err());
+
+        handle.shutdown().await.unwrap();
     }
 
-    /// Check for expired timers and fire them
-    async fn check_expired_timers(&mut self) {
-        let now = Instant::now();
-        let mut expired_timers = Vec::new();
+    #[tokio::test]
+    async fn test_scale_remaining() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
 
-        // Collect expired timers
-        for (name, expires_at) in &self.timers {
-            if *expires_at <= now {
-                expired_timers.push(name.clone());
-            }
-        }
+        handle
+            .set_timer("a".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        handle
+            .set_timer("b".to_string(), Duration::from_secs(20))
+            .await
+            .unwrap();
 
-        // Process expired timers
-        for name in expired_timers {
-            // Remove from storage
-            self.timers.remove(&name);
+        handle.scale_remaining(0.5).await.unwrap();
+        let remaining = handle
+            .get_remaining("b".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(remaining > Duration::from_secs(9) && remaining <= Duration::from_secs(10));
 
-            // Send expiration event
-            //log::debug!("Timer '{}' expired in manager '{}'", name, self.name);
+        handle.scale_remaining(-1.0).await.unwrap();
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::ScaleRejected { factor } => assert_eq!(factor, -1.0),
+            other => panic!("unexpected event: {:?}", other),
+        }
 
-            // Use try_send to avoid blocking if event channel is full
-            if let Err(e) = self
-                .event_tx
-                .try_send(TimerEvent::TimerExpired { name: name.clone() })
-            {
-                match e {
-                    mpsc::error::TrySendError::Full(_) => {
-                        log::warn!(
-                            "Event channel full, dropping timer expiration for '{}'",
-                            name
-                        );
-                    }
-                    mpsc::error::TrySendError::Closed(_) => {
-                        log::warn!(
-                            "Event channel closed, cannot send timer expiration for '{}'",
-                            name
-                        );
-                        break;
-                    }
-                }
+        handle.scale_remaining(0.0).await.unwrap();
+        for expected in ["a", "b"] {
+            match handle.recv_event().await.unwrap() {
+                TimerEvent::TimerExpired { name, .. } => assert_eq!(name, expected),
+                other => panic!("unexpected event: {:?}", other),
             }
         }
-    }
-}
 
-impl TimerHandle {
-    /// Set a timer (creates new or updates existing)
-    pub async fn set_timer(
-        &self,
-        name: String,
-        duration: Duration,
-    ) -> Result<(), mpsc::error::SendError<TimerCommand>> {
-        self.command_tx
-            .send(TimerCommand::SetTimer { name, duration })
-            .await
+        handle.shutdown().await.unwrap();
     }
 
-    /// Set a timer (non-blocking)
-    pub fn try_set_timer(
-        &self,
-        name: String,
-        duration: Duration,
-    ) -> Result<(), mpsc::error::TrySendError<TimerCommand>> {
-        self.command_tx
-            .try_send(TimerCommand::SetTimer { name, duration })
-    }
+    #[tokio::test]
+    async fn test_event_stream() {
+        use futures::StreamExt;
 
-    /// Cancel a specific timer
-    pub async fn cancel_timer(
-        &self,
-        name: String,
-    ) -> Result<(), mpsc::error::SendError<TimerCommand>> {
-        self.command_tx
-            .send(TimerCommand::CancelTimer { name })
-            .await
-    }
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
 
-    /// Cancel a specific timer (non-blocking)
-    pub fn try_cancel_timer(
-        &self,
-        name: String,
-    ) -> Result<(), mpsc::error::TrySendError<TimerCommand>> {
-        self.command_tx.try_send(TimerCommand::CancelTimer { name })
-    }
+        for (name, ms) in [("a", 10), ("b", 20), ("c", 30)] {
+            handle
+                .set_timer(name.to_string(), Duration::from_millis(ms))
+                .await
+                .unwrap();
+        }
+        let first: Vec<String> = handle
+            .event_stream()
+            .filter_map(|event| async move {
+                match event {
+                    TimerEvent::TimerExpired { name, .. } => Some(name),
+                    _ => None,
+                }
+            })
+            .take(2)
+            .collect()
+            .await;
+        assert_eq!(first, vec!["a", "b"]);
 
-    /// Cancel all timers
-    pub async fn cancel_all_timers(&self) -> Result<(), mpsc::error::SendError<TimerCommand>> {
-        self.command_tx.send(TimerCommand::CancelAllTimers).await
+        let commander = handle.command_handle();
+        let mut events = Box::pin(handle.into_event_stream());
+        match events.next().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "c"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        commander.shutdown().await.unwrap();
+        assert!(events.next().await.is_none());
     }
 
-    /// Cancel all timers (non-blocking)
-    pub fn try_cancel_all_timers(&self) -> Result<(), mpsc::error::TrySendError<TimerCommand>> {
-        self.command_tx.try_send(TimerCommand::CancelAllTimers)
-    }
+    #[tokio::test]
+    async fn test_heartbeat_lag_detection() {
+        let (manager, mut handle) = TimerManager::builder("test")
+            .heartbeat(Duration::from_millis(10))
+            .heartbeat_lag_detection(Duration::from_millis(20))
+            .build();
 
-    /// Shutdown the timer manager
-    pub async fn shutdown(&self) -> Result<(), mpsc::error::SendError<TimerCommand>> {
-        self.command_tx.send(TimerCommand::Shutdown).await
-    }
+        manager.check_heartbeat_lag(tokio_now());
+        assert!(handle.try_recv_event().is_err());
 
-    /// Shutdown the timer manager (non-blocking)
-    pub fn try_shutdown(&self) -> Result<(), mpsc::error::TrySendError<TimerCommand>> {
-        self.command_tx.try_send(TimerCommand::Shutdown)
+        manager.check_heartbeat_lag(tokio_now() - Duration::from_millis(35));
+        match handle.try_recv_event().unwrap() {
+            TimerEvent::HeartbeatLagged { skipped, elapsed } => {
+                assert!(skipped >= 3);
+                assert!(elapsed >= Duration::from_millis(35));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert_eq!(
+            manager.effective_config().heartbeat_lag,
+            Some(Duration::from_millis(20))
+        );
     }
 
-    /// Receive the next timer event (blocking)
-    pub async fn recv_event(&mut self) -> Option<TimerEvent> {
-        self.event_rx.recv().await
-    }
+    #[tokio::test]
+    async fn test_snapshot() {
+        let (manager, handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
 
-    /// Try to receive a timer event (non-blocking)
-    pub fn try_recv_event(&mut self) -> Result<TimerEvent, mpsc::error::TryRecvError> {
-        self.event_rx.try_recv()
-    }
-}
+        for (name, secs) in [("late", 30), ("early", 10), ("middle", 20)] {
+            handle
+                .set_timer(name.to_string(), Duration::from_secs(secs))
+                .await
+                .unwrap();
+        }
+        let snapshot = handle.snapshot().await.unwrap();
+        let names: Vec<&str> = snapshot.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["early", "middle", "late"]);
+        assert!(snapshot[0].1 > Duration::from_secs(9) && snapshot[0].1 <= Duration::from_secs(10));
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tokio::time::sleep;
+        handle.shutdown().await.unwrap();
+    }
 
     #[tokio::test]
-    async fn test_timer_basic_functionality() {
-        let cancel_token = CancellationToken::new();
-        let (manager, mut handle) = TimerManager::new(
-            "test".to_string(),
-            Duration::from_millis(10),
-            10, // command buffer size
-            10, // event buffer size
-            cancel_token.clone(),
-        );
-
-        // Spawn the manager
+    async fn test_event_sink() {
+        let (sink_tx, mut sink_rx) = mpsc::unbounded_channel();
+        let (manager, handle) = TimerManager::new_with_sink("test", move |event| {
+            let _ = sink_tx.send(event);
+        });
         tokio::spawn(manager.run());
 
-        // Set a short timer
         handle
-            .set_timer("test_timer".to_string(), Duration::from_millis(50))
+            .set_timer("timer".to_string(), Duration::from_millis(10))
             .await
             .unwrap();
-
-        // Wait for expiration
-        let event = handle.recv_event().await.unwrap();
-        match event {
-            TimerEvent::TimerExpired { name } => {
-                assert_eq!(name, "test_timer");
-            }
+        match sink_rx.recv().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "timer"),
+            other => panic!("unexpected event: {:?}", other),
         }
 
         handle.shutdown().await.unwrap();
     }
 
     #[tokio::test]
-    async fn test_timer_cancel() {
-        let cancel_token = CancellationToken::new();
-        let (manager, mut handle) = TimerManager::new(
-            "test".to_string(),
-            Duration::from_millis(10),
-            10,
-            10,
-            cancel_token.clone(),
-        );
-
+    async fn test_coalesce_sets() {
+        let (manager, handle) = TimerManager::builder("test")
+            .coalesce_sets(Duration::from_secs(1))
+            .build();
         tokio::spawn(manager.run());
 
-        // Set a timer
         handle
-            .set_timer("test_timer".to_string(), Duration::from_millis(100))
+            .set_timer("x".to_string(), Duration::from_secs(5))
             .await
             .unwrap();
+        handle.ping().await.unwrap();
+        let first = handle
+            .get_remaining("x".to_string())
+            .await
+            .unwrap()
+            .unwrap();
 
-        // Cancel it immediately
-        handle.cancel_timer("test_timer".to_string()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        handle
+            .set_timer("x".to_string(), Duration::from_secs(5))
+            .await
+            .unwrap();
+        let repeated = handle
+            .get_remaining("x".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(repeated < first - Duration::from_millis(40));
 
-        // Wait a bit to ensure it doesn't fire
-        sleep(Duration::from_millis(150)).await;
+        handle
+            .set_timer("x".to_string(), Duration::from_secs(8))
+            .await
+            .unwrap();
+        let changed = handle
+            .get_remaining("x".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(changed > Duration::from_secs(7));
 
-        // Should not receive any events
-        assert!(handle.try_recv_event().is_err());
+        let metrics = handle.metrics().await.unwrap();
+        assert_eq!(metrics.timers_set, 2);
 
         handle.shutdown().await.unwrap();
     }
 
     #[tokio::test]
-    async fn test_bounded_channel_backpressure() {
-        let cancel_token = CancellationToken::new();
-        let (manager, handle) = TimerManager::new(
-            "test".to_string(),
-            Duration::from_millis(10),
-            2, // small command buffer
-            2, // small event buffer
-            cancel_token.clone(),
-        );
+    async fn test_set_timer_with_jitter() {
+        let deadlines = |seed| async move {
+            let (manager, handle) = TimerManager::builder("test").rng_seed(seed).build();
+            tokio::spawn(manager.run());
+            for i in 0..20 {
+                handle
+                    .set_timer_with_jitter(
+                        format!("backoff:{}", i),
+                        Duration::from_secs(10),
+                        Duration::from_secs(5),
+                    )
+                    .await
+                    .unwrap();
+            }
+            let mut remaining: Vec<Duration> = handle
+                .snapshot()
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|(_, remaining)| remaining)
+                .collect();
+            handle.shutdown().await.unwrap();
+            remaining.sort();
+            remaining
+        };
+
+        let first = deadlines(7).await;
+        assert!(first[0] > Duration::from_secs(9));
+        assert!(first[19] <= Duration::from_secs(15));
+        assert!(first[19] - first[0] > Duration::from_secs(1));
+
+        // The same seed spreads the timers the same way
+        let second = deadlines(7).await;
+        for (a, b) in first.iter().zip(&second) {
+            assert!(a.abs_diff(*b) < Duration::from_millis(100));
+        }
+    }
 
+    #[tokio::test]
+    async fn test_rename_timer() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
         tokio::spawn(manager.run());
 
-        // Fill up the command channel
         handle
-            .set_timer("timer1".to_string(), Duration::from_millis(50))
+            .set_timer("provisional".to_string(), Duration::from_millis(40))
             .await
             .unwrap();
         handle
-            .set_timer("timer2".to_string(), Duration::from_millis(50))
+            .set_timer("taken".to_string(), Duration::from_secs(10))
+            .await
+            .unwrap();
+        let before = handle
+            .get_remaining("provisional".to_string())
             .await
+            .unwrap()
             .unwrap();
 
-        // This should work with try_send
-        let _result = handle.try_set_timer("timer3".to_string(), Duration::from_millis(50));
-        // Might succeed or fail depending on timing, but shouldn't panic
+        handle
+            .rename_timer("provisional".to_string(), "taken".to_string())
+            .await
+            .unwrap();
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerRejected { name, reason } => {
+                assert_eq!(name, "taken");
+                assert_eq!(reason, RejectReason::AlreadyExists);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        handle
+            .rename_timer("missing".to_string(), "other".to_string())
+            .await
+            .unwrap();
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerNotFound { name } => assert_eq!(name, "missing"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        handle
+            .rename_timer("provisional".to_string(), "permanent".to_string())
+            .await
+            .unwrap();
+        let after = handle
+            .get_remaining("permanent".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(after <= before);
+        assert!(!handle
+            .contains_timer("provisional".to_string())
+            .await
+            .unwrap());
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerExpired { name, .. } => assert_eq!(name, "permanent"),
+            other => panic!("unexpected event: {:?}", other),
+        }
 
         handle.shutdown().await.unwrap();
     }
 
     #[tokio::test]
-    async fn test_cancellation_token() {
-        let cancel_token = CancellationToken::new();
-        let (manager, mut handle) = TimerManager::new(
-            "test".to_string(),
-            Duration::from_millis(10),
-            10,
-            10,
-            cancel_token.clone(),
-        );
-
-        let manager_task = tokio::spawn(manager.run());
+    async fn test_recurring_timer_rejects_zero_interval() {
+        let (manager, mut handle) = TimerManager::builder("test").build();
+        tokio::spawn(manager.run());
 
-        // Set a timer that should expire
         handle
-            .set_timer("timer1".to_string(), Duration::from_millis(50))
+            .set_recurring_timer("tick".to_string(), Duration::ZERO)
             .await
             .unwrap();
-
-        // Cancel the token
-        cancel_token.cancel();
-
-        // Wait for the manager to shut down
-        let _ = manager_task.await;
-
-        // Try to set a timer after cancellation - this should fail
-        let result = handle.try_set_timer("timer2".to_string(), Duration::from_millis(50));
-        assert!(
-            result.is_err(),
-            "Setting timer after cancellation should fail"
+        match handle.recv_event().await.unwrap() {
+            TimerEvent::TimerRejected { name, reason } => {
+                assert_eq!(name, "tick");
+                assert_eq!(reason, RejectReason::IntervalTooShort);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert_eq!(
+            handle.get_remaining("tick".to_string()).await.unwrap(),
+            None
         );
 
-        // Should not receive any timer events since manager was cancelled
-        assert!(
-            handle.try_recv_event().is_err(),
-            "Should not receive events after cancellation"
-        );
+        handle.shutdown().await.unwrap();
     }
 
     #[tokio::test]
-    async fn test_cancellation_during_timer_operation() {
-        let cancel_token = CancellationToken::new();
-        let (manager, mut handle) = TimerManager::new(
-            "test".to_string(),
-            Duration::from_millis(10),
-            10,
-            10,
-            cancel_token.clone(),
-        );
+    async fn test_stops_when_all_senders_dropped() {
+        use futures::StreamExt;
 
-        let manager_task = tokio::spawn(manager.run());
+        let (manager, handle) = TimerManager::builder("test").build();
+        let running = tokio::spawn(manager.run());
 
-        // Set multiple timers
         handle
-            .set_timer("timer1".to_string(), Duration::from_millis(100))
+            .set_timer("timer".to_string(), Duration::from_secs(10))
             .await
             .unwrap();
-        handle
-            .set_timer("timer2".to_string(), Duration::from_millis(200))
+        let events = handle.into_event_stream();
+        let held = tokio::time::timeout(Duration::from_secs(1), running)
             .await
+            .expect("manager kept running without senders")
             .unwrap();
-
-        // Wait a bit to ensure timers are set
-        sleep(Duration::from_millis(20)).await;
-
-        // Cancel the token before timers expire
-        cancel_token.cancel();
-
-        // Wait for the manager to shut down
-        let _ = manager_task.await;
-
-        // Timers should not fire since manager was cancelled
-        assert!(
-            handle.try_recv_event().is_err(),
-            "No timer events should be received after cancellation"
-        );
-
-        // Subsequent operations should fail
-        let result = handle.try_set_timer("timer3".to_string(), Duration::from_millis(50));
-        assert!(result.is_err(), "Operations after cancellation should fail");
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].0, "timer");
+        assert_eq!(events.count().await, 0);
     }
 }